@@ -0,0 +1,78 @@
+use crate::hypothesis::read_hypothesis;
+use clap::ValueEnum;
+use schema_harvester::{Change, diff_hypotheses};
+use std::error::Error;
+
+#[derive(Clone, Copy, Debug, Default, ValueEnum)]
+pub enum DiffFormat {
+    /// one line per change
+    #[default]
+    Human,
+    /// a JSON array of changes, with `path`, `change` and its details
+    Json,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct DiffArgs {
+    /// the old schema, a hypothesis saved via `--output hypothesis` or a JSON schema
+    old: String,
+
+    /// the new schema, a hypothesis saved via `--output hypothesis` or a JSON schema
+    new: String,
+
+    #[clap(short, long, value_enum, default_value_t)]
+    /// format to list the changes in
+    output: DiffFormat,
+}
+
+/// list the structural changes between two schemas
+pub fn diff(args: DiffArgs) -> Result<(), Box<dyn Error>> {
+    let old = read_hypothesis(&args.old)?;
+    let new = read_hypothesis(&args.new)?;
+
+    let changes = diff_hypotheses(&old, &new);
+
+    crate::print(&render(&changes, args.output)?)
+}
+
+fn render(changes: &[Change], format: DiffFormat) -> Result<String, Box<dyn Error>> {
+    match format {
+        DiffFormat::Human if changes.is_empty() => Ok("no changes".to_string()),
+        DiffFormat::Human => Ok(changes
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join("\n")),
+        DiffFormat::Json => Ok(serde_json::to_string_pretty(changes)?),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{DiffFormat, render};
+    use schema_harvester::{Change, ChangeKind};
+
+    #[test]
+    fn test_render() {
+        let changes = vec![
+            Change {
+                path: "$.id".to_string(),
+                kind: ChangeKind::TypeWidened {
+                    from: "integer".to_string(),
+                    to: "integer | string".to_string(),
+                },
+            },
+            Change {
+                path: "$.note".to_string(),
+                kind: ChangeKind::BecameOptional,
+            },
+        ];
+
+        assert_eq!(
+            render(&changes, DiffFormat::Human).unwrap(),
+            "$.id: type widened from integer to integer | string\n$.note: became optional"
+        );
+        assert_eq!(render(&[], DiffFormat::Human).unwrap(), "no changes");
+        assert_eq!(render(&[], DiffFormat::Json).unwrap(), "[]");
+    }
+}
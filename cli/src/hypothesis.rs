@@ -0,0 +1,31 @@
+use schema_harvester::{
+    HYPOTHESIS_FORMAT, SchemaHypothesis, load_hypothesis, parse_schema, render_schema,
+};
+use serde_json::Value;
+use std::error::Error;
+
+/// read a hypothesis saved via `--output hypothesis` or a JSON schema
+pub fn read_hypothesis(path: &str) -> Result<SchemaHypothesis, Box<dyn Error>> {
+    let content = std::fs::read_to_string(path).map_err(|e| format!("{path}: {e}"))?;
+    let document: Value = serde_json::from_str(&content).map_err(|e| format!("{path}: {e}"))?;
+
+    let hypothesis = if document.get("format") == Some(&Value::from(HYPOTHESIS_FORMAT)) {
+        load_hypothesis(&content).map_err(|e| format!("{path}: {e}"))?
+    } else {
+        parse_schema(&document).map_err(|e| format!("{path}: {e}"))?
+    };
+
+    Ok(hypothesis)
+}
+
+/// read a JSON schema as is, or render a hypothesis saved via `--output hypothesis`
+pub fn read_schema(path: &str) -> Result<Value, Box<dyn Error>> {
+    let content = std::fs::read_to_string(path).map_err(|e| format!("{path}: {e}"))?;
+    let document: Value = serde_json::from_str(&content).map_err(|e| format!("{path}: {e}"))?;
+
+    if document.get("format") != Some(&Value::from(HYPOTHESIS_FORMAT)) {
+        return Ok(document);
+    }
+    let hypothesis = load_hypothesis(&content).map_err(|e| format!("{path}: {e}"))?;
+    Ok(serde_json::from_str(&render_schema(&hypothesis))?)
+}
@@ -0,0 +1,551 @@
+use clap::ValueEnum;
+use schema_harvester::{PayloadFormat, read_document};
+use serde::Deserialize;
+use serde::de::IgnoredAny;
+use serde_json::Value;
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+use std::io::{BufRead, BufReader, Cursor, Read};
+use std::path::Path;
+
+pub type Documents = Box<dyn Iterator<Item = Result<Value, InvalidDocument>>>;
+
+/// a document that couldn't be read, with its location (as far as known)
+#[derive(Debug)]
+pub struct InvalidDocument {
+    /// line the document starts at, 1-based
+    pub line: Option<u64>,
+    /// byte offset the document starts at
+    pub offset: Option<u64>,
+    /// the document as read, for line-delimited input
+    pub raw: Option<Vec<u8>>,
+    pub error: Box<dyn Error + Send + Sync>,
+}
+
+impl InvalidDocument {
+    fn new(error: impl Into<Box<dyn Error + Send + Sync>>) -> Self {
+        Self {
+            line: None,
+            offset: None,
+            raw: None,
+            error: error.into(),
+        }
+    }
+
+    fn at_offset(offset: u64, error: impl Into<Box<dyn Error + Send + Sync>>) -> Self {
+        Self {
+            offset: Some(offset),
+            ..Self::new(error)
+        }
+    }
+}
+
+impl Display for InvalidDocument {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match (self.line, self.offset) {
+            (Some(line), Some(offset)) => write!(f, "line {line} (byte {offset}): ")?,
+            (Some(line), None) => write!(f, "line {line}: ")?,
+            (None, Some(offset)) => write!(f, "byte {offset}: ")?,
+            (None, None) => {}
+        }
+        write!(f, "{}", self.error)
+    }
+}
+
+impl Error for InvalidDocument {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(self.error.as_ref())
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum InputFormat {
+    /// concatenated or line-delimited JSON documents
+    #[default]
+    Json,
+    /// YAML, with multiple documents separated by `---`
+    Yaml,
+    /// one TOML document
+    Toml,
+    /// one JSON5 document
+    Json5,
+    /// comma-separated values with a header row, one document per row
+    Csv,
+    /// tab-separated values with a header row, one document per row
+    Tsv,
+    /// concatenated MessagePack documents
+    MessagePack,
+    /// concatenated CBOR documents (a CBOR sequence)
+    Cbor,
+    /// concatenated BSON documents, e.g. as written by `mongodump`
+    Bson,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct CsvArgs {
+    #[clap(long)]
+    /// delimiter for `--input-format csv|tsv`, defaults to `,` resp. tab
+    csv_delimiter: Option<char>,
+
+    #[clap(long, default_value_t = '"')]
+    /// quote character for `--input-format csv|tsv`
+    csv_quote: char,
+
+    #[clap(long)]
+    /// don't treat quotes specially for `--input-format csv|tsv`
+    csv_no_quoting: bool,
+
+    #[clap(long = "csv-null")]
+    /// cell values to read as null for `--input-format csv|tsv` besides empty cells, may be given several times
+    csv_null: Vec<String>,
+}
+
+impl InputFormat {
+    /// detect the format by the file extension
+    pub fn detect(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "json" | "jsonl" | "ndjson" => Some(Self::Json),
+            "yaml" | "yml" => Some(Self::Yaml),
+            "toml" => Some(Self::Toml),
+            "json5" => Some(Self::Json5),
+            "csv" => Some(Self::Csv),
+            "tsv" | "tab" => Some(Self::Tsv),
+            "msgpack" | "mpk" => Some(Self::MessagePack),
+            "cbor" => Some(Self::Cbor),
+            "bson" => Some(Self::Bson),
+            _ => None,
+        }
+    }
+}
+
+/// read the documents of the given format, converted to JSON
+pub fn read_documents(
+    mut reader: Box<dyn Read>,
+    format: InputFormat,
+    csv: &CsvArgs,
+) -> Result<Documents, Box<dyn Error>> {
+    let documents: Documents = match format {
+        InputFormat::Json => read_concatenated_json(reader),
+        InputFormat::Yaml => stop_after_error(Box::new(
+            serde_yaml_ng::Deserializer::from_reader(reader)
+                .map(|document| Value::deserialize(document).map_err(InvalidDocument::new)),
+        )),
+        InputFormat::Toml | InputFormat::Json5 => {
+            let mut content = String::new();
+            let document = match reader.read_to_string(&mut content) {
+                Err(e) => Err(InvalidDocument::new(e)),
+                Ok(_) if format == InputFormat::Toml => parse_toml(&content),
+                Ok(_) => json5::from_str::<Value>(&content).map_err(InvalidDocument::new),
+            };
+            Box::new(std::iter::once(document))
+        }
+        InputFormat::Csv | InputFormat::Tsv => {
+            let default_delimiter = if format == InputFormat::Csv {
+                ','
+            } else {
+                '\t'
+            };
+            read_csv(reader, csv, default_delimiter)?
+        }
+        InputFormat::MessagePack => read_binary(reader, PayloadFormat::MessagePack),
+        InputFormat::Cbor => read_binary(reader, PayloadFormat::Cbor),
+        InputFormat::Bson => read_binary(reader, PayloadFormat::Bson),
+    };
+
+    Ok(documents)
+}
+
+fn read_concatenated_json(reader: Box<dyn Read>) -> Documents {
+    let mut documents = serde_json::Deserializer::from_reader(reader).into_iter::<Value>();
+    Box::new(std::iter::from_fn(move || {
+        let document = documents.next()?;
+        // after an error, the offset is the start of the invalid document
+        let offset = documents.byte_offset() as u64;
+        Some(document.map_err(|e| InvalidDocument::at_offset(offset, e)))
+    }))
+}
+
+/// read line-delimited JSON line by line, so reading continues after invalid lines, and other JSON (e.g.
+/// pretty-printed documents) as concatenated documents, which stops at the first invalid one
+///
+/// The input is taken as line-delimited unless its first line is the start of a document only.
+pub fn read_json(reader: Box<dyn Read>) -> Result<Documents, Box<dyn Error>> {
+    let mut reader = BufReader::new(reader);
+    let mut head = vec![];
+    while head.trim_ascii().is_empty() {
+        if reader.read_until(b'\n', &mut head)? == 0 {
+            break;
+        }
+    }
+    let multi_line = serde_json::from_slice::<IgnoredAny>(&head).is_err_and(|e| e.is_eof());
+
+    let reader = Box::new(Cursor::new(head).chain(reader));
+    Ok(if multi_line {
+        read_concatenated_json(reader)
+    } else {
+        read_json_lines(reader)
+    })
+}
+
+/// read line-delimited JSON (one document per line), so invalid documents can be skipped
+pub fn read_json_lines(reader: Box<dyn Read>) -> Documents {
+    let mut reader = BufReader::new(reader);
+    let mut line = 0;
+    let mut offset = 0;
+    let mut failed = false;
+
+    Box::new(std::iter::from_fn(move || {
+        while !failed {
+            let mut raw = vec![];
+            let length = match reader.read_until(b'\n', &mut raw) {
+                Ok(0) => return None,
+                Ok(length) => length as u64,
+                Err(e) => {
+                    // the input can't be read any further
+                    failed = true;
+                    return Some(Err(InvalidDocument {
+                        line: Some(line + 1),
+                        ..InvalidDocument::at_offset(offset, e)
+                    }));
+                }
+            };
+            line += 1;
+            offset += length;
+
+            let raw = raw.trim_ascii_end();
+            if raw.trim_ascii_start().is_empty() {
+                continue;
+            }
+            return Some(serde_json::from_slice(raw).map_err(|e| InvalidDocument {
+                line: Some(line),
+                raw: Some(raw.to_vec()),
+                ..InvalidDocument::at_offset(offset - length, e)
+            }));
+        }
+        None
+    }))
+}
+
+/// end the documents after the first invalid one, for formats without a way to find the start of the next
+/// document
+fn stop_after_error(mut documents: Documents) -> Documents {
+    let mut failed = false;
+    Box::new(std::iter::from_fn(move || {
+        if failed {
+            return None;
+        }
+        let document = documents.next();
+        failed = matches!(document, Some(Err(_)));
+        document
+    }))
+}
+
+fn read_binary(reader: Box<dyn Read>, format: PayloadFormat) -> Documents {
+    let mut reader = BufReader::new(Counting {
+        inner: reader,
+        count: 0,
+    });
+
+    stop_after_error(Box::new(std::iter::from_fn(move || {
+        let offset = reader.get_ref().count - reader.buffer().len() as u64;
+        read_document(&mut reader, format)
+            .map_err(|e| InvalidDocument::at_offset(offset, e))
+            .transpose()
+    })))
+}
+
+/// a reader that counts the bytes read
+struct Counting<R> {
+    inner: R,
+    count: u64,
+}
+
+impl<R: Read> Read for Counting<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let length = self.inner.read(buf)?;
+        self.count += length as u64;
+        Ok(length)
+    }
+}
+
+fn read_csv(
+    reader: Box<dyn Read>,
+    options: &CsvArgs,
+    default_delimiter: char,
+) -> Result<Documents, Box<dyn Error>> {
+    let ascii = |c: char, option: &str| {
+        u8::try_from(c).map_err(|_| format!("{option} must be an ASCII character"))
+    };
+
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(ascii(
+            options.csv_delimiter.unwrap_or(default_delimiter),
+            "--csv-delimiter",
+        )?)
+        .quote(ascii(options.csv_quote, "--csv-quote")?)
+        .quoting(!options.csv_no_quoting)
+        .from_reader(reader);
+    let headers = reader.headers()?.clone();
+    let null_tokens = options.csv_null.clone();
+
+    Ok(Box::new(reader.into_records().map(move |record| {
+        let record = record.map_err(|e| {
+            let position = e.position();
+            InvalidDocument {
+                line: position.map(|position| position.line()),
+                offset: position.map(|position| position.byte()),
+                ..InvalidDocument::new(e)
+            }
+        })?;
+        Ok(Value::Object(
+            headers
+                .iter()
+                .zip(record.iter())
+                .map(|(header, cell)| (header.to_string(), infer_cell(cell, &null_tokens)))
+                .collect(),
+        ))
+    })))
+}
+
+/// the JSON value of a cell, strings are left for format detection
+fn infer_cell(cell: &str, null_tokens: &[String]) -> Value {
+    if cell.is_empty() || null_tokens.iter().any(|token| token == cell) {
+        return Value::Null;
+    }
+    if cell.eq_ignore_ascii_case("true") || cell.eq_ignore_ascii_case("false") {
+        return Value::Bool(cell.eq_ignore_ascii_case("true"));
+    }
+    if leading_zero(cell) {
+        return Value::String(cell.to_string());
+    }
+    if let Ok(i) = cell.parse::<i64>() {
+        return Value::from(i);
+    }
+    match cell.parse::<f64>() {
+        // e.g. "inf" or "NaN" are strings rather than numbers
+        Ok(f) if f.is_finite() => Value::from(f),
+        _ => Value::String(cell.to_string()),
+    }
+}
+
+/// whether a number has leading zeros, e.g. zip codes or phone numbers, which would lose them as numbers
+fn leading_zero(cell: &str) -> bool {
+    let digits = cell.strip_prefix(['-', '+']).unwrap_or(cell).as_bytes();
+    digits.len() > 1 && digits[0] == b'0' && digits[1].is_ascii_digit()
+}
+
+fn parse_toml(content: &str) -> Result<Value, InvalidDocument> {
+    let table: toml::Table = toml::from_str(content).map_err(InvalidDocument::new)?;
+    Ok(toml_to_json(toml::Value::Table(table)))
+}
+
+/// convert TOML to JSON, with date-times as strings (so their format is detected)
+fn toml_to_json(value: toml::Value) -> Value {
+    match value {
+        toml::Value::String(s) => Value::String(s),
+        toml::Value::Integer(i) => Value::from(i),
+        toml::Value::Float(f) => Value::from(f),
+        toml::Value::Boolean(b) => Value::Bool(b),
+        toml::Value::Datetime(datetime) => Value::String(datetime.to_string()),
+        toml::Value::Array(array) => Value::Array(array.into_iter().map(toml_to_json).collect()),
+        toml::Value::Table(table) => Value::Object(
+            table
+                .into_iter()
+                .map(|(key, value)| (key, toml_to_json(value)))
+                .collect(),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{CsvArgs, InputFormat, read_documents, read_json, read_json_lines};
+    use serde_json::{Value, json};
+    use std::path::Path;
+
+    fn csv_args() -> CsvArgs {
+        CsvArgs {
+            csv_delimiter: None,
+            csv_quote: '"',
+            csv_no_quoting: false,
+            csv_null: vec![],
+        }
+    }
+
+    fn documents(content: &'static str, format: InputFormat) -> Vec<Value> {
+        documents_with(content, format, &csv_args())
+    }
+
+    fn documents_with(content: &'static str, format: InputFormat, csv: &CsvArgs) -> Vec<Value> {
+        read_documents(Box::new(content.as_bytes()), format, csv)
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_detect() {
+        assert_eq!(
+            InputFormat::detect(Path::new("values.yml")),
+            Some(InputFormat::Yaml)
+        );
+        assert_eq!(
+            InputFormat::detect(Path::new("Cargo.TOML")),
+            Some(InputFormat::Toml)
+        );
+        assert_eq!(
+            InputFormat::detect(Path::new("dump.ndjson")),
+            Some(InputFormat::Json)
+        );
+        assert_eq!(InputFormat::detect(Path::new("README")), None);
+    }
+
+    #[test]
+    fn test_yaml_documents() {
+        let yaml =
+            "kind: Service\nmetadata:\n  name: a\n---\nkind: Deployment\nspec:\n  replicas: 2\n";
+
+        assert_eq!(
+            documents(yaml, InputFormat::Yaml),
+            vec![
+                json!({"kind": "Service", "metadata": {"name": "a"}}),
+                json!({"kind": "Deployment", "spec": {"replicas": 2}}),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_toml() {
+        let toml = "name = \"a\"\nreleased = 2000-01-01\n\n[server]\nport = 80\nratio = 0.5\n";
+
+        assert_eq!(
+            documents(toml, InputFormat::Toml),
+            vec![
+                json!({"name": "a", "released": "2000-01-01", "server": {"port": 80, "ratio": 0.5}})
+            ]
+        );
+    }
+
+    #[test]
+    fn test_json5() {
+        let json5 = "// comment\n{name: 'a', tags: ['b',], hex: 0x10,}";
+
+        assert_eq!(
+            documents(json5, InputFormat::Json5),
+            vec![json!({"name": "a", "tags": ["b"], "hex": 16})]
+        );
+    }
+
+    #[test]
+    fn test_csv() {
+        let csv = "id,name,price,active,created,note,zip\n1,\"a, b\",1.5,true,2000-01-01,,007\n2,c,2,FALSE,2000-01-02,x,0\n3,d,0.5,true,2000-01-03,x,-01.5\n";
+
+        assert_eq!(
+            documents(csv, InputFormat::Csv),
+            vec![
+                json!({"id": 1, "name": "a, b", "price": 1.5, "active": true, "created": "2000-01-01", "note": null, "zip": "007"}),
+                json!({"id": 2, "name": "c", "price": 2, "active": false, "created": "2000-01-02", "note": "x", "zip": 0}),
+                json!({"id": 3, "name": "d", "price": 0.5, "active": true, "created": "2000-01-03", "note": "x", "zip": "-01.5"}),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tsv_with_options() {
+        let tsv = "id;value\n1;NULL\n2;'a;b'\n3;\n";
+        let options = CsvArgs {
+            csv_delimiter: Some(';'),
+            csv_quote: '\'',
+            csv_null: vec!["NULL".to_string()],
+            ..csv_args()
+        };
+
+        assert_eq!(
+            documents_with(tsv, InputFormat::Tsv, &options),
+            vec![
+                json!({"id": 1, "value": null}),
+                json!({"id": 2, "value": "a;b"}),
+                json!({"id": 3, "value": null}),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_message_pack() {
+        let content: &'static [u8] = &[0x81, 0xa2, b'i', b'd', 0x01, 0xc0];
+
+        let actual: Vec<Value> =
+            read_documents(Box::new(content), InputFormat::MessagePack, &csv_args())
+                .unwrap()
+                .collect::<Result<_, _>>()
+                .unwrap();
+
+        assert_eq!(actual, vec![json!({"id": 1}), json!(null)]);
+    }
+
+    #[test]
+    fn test_json_lines_skip_invalid() {
+        let content: &'static [u8] = b"{\"id\": 1}\r\n\n{\"id\": \n[2]\n";
+
+        let actual: Vec<_> = read_json_lines(Box::new(content)).collect();
+
+        assert_eq!(actual.len(), 3);
+        assert_eq!(actual[0].as_ref().unwrap(), &json!({"id": 1}));
+        let invalid = actual[1].as_ref().unwrap_err();
+        assert_eq!(
+            (invalid.line, invalid.offset, invalid.raw.as_deref()),
+            (Some(3), Some(12), Some(&b"{\"id\":"[..]))
+        );
+        assert_eq!(actual[2].as_ref().unwrap(), &json!([2]));
+    }
+
+    #[test]
+    fn test_json_lines_or_concatenated() {
+        let read = |content: &'static str| -> Vec<bool> {
+            read_json(Box::new(content.as_bytes()))
+                .unwrap()
+                .map(|document| document.is_ok())
+                .collect()
+        };
+
+        assert_eq!(
+            read("\n{\"a\": 1}\n{\"a\":\n{\"a\": \"x\"}\n"),
+            vec![true, false, true]
+        );
+        assert_eq!(
+            read("{\n  \"a\": 1\n}\n{\n  \"a\": 2\n}\n"),
+            vec![true, true]
+        );
+        assert_eq!(read("{\n  \"a\": 1\n}\n{\n  \"a\":\n"), vec![true, false]);
+        assert!(read("").is_empty());
+    }
+
+    #[test]
+    fn test_invalid_locations() {
+        let json: &'static [u8] = b"{\"id\": 1} {\"id\" 2}";
+        let mut documents = read_documents(Box::new(json), InputFormat::Json, &csv_args()).unwrap();
+        assert!(documents.next().unwrap().is_ok());
+        assert_eq!(documents.next().unwrap().unwrap_err().offset, Some(10));
+        assert!(documents.next().is_none());
+
+        // the rest of binary input can't be read after an invalid document
+        let message_pack: &'static [u8] = &[0x01, 0xa5, b'a'];
+        let documents: Vec<_> = read_documents(
+            Box::new(message_pack),
+            InputFormat::MessagePack,
+            &csv_args(),
+        )
+        .unwrap()
+        .collect();
+        assert_eq!(documents.len(), 2);
+        assert_eq!(documents[1].as_ref().unwrap_err().offset, Some(1));
+
+        let csv: &'static [u8] = b"id,name\n1,a\n2\n3,c\n";
+        let documents: Vec<_> = read_documents(Box::new(csv), InputFormat::Csv, &csv_args())
+            .unwrap()
+            .collect();
+        assert_eq!(documents.len(), 3);
+        assert_eq!(documents[1].as_ref().unwrap_err().line, Some(3));
+    }
+}
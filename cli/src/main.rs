@@ -1,51 +1,347 @@
-use clap::Parser;
-use schema_harvester::model::NodeType;
-use schema_harvester::{SchemaHypothesis, render_schema};
+use crate::diff::DiffArgs;
+use crate::hypothesis::read_hypothesis;
+use crate::input::{CsvArgs, InputFormat, read_documents, read_json_lines};
+use crate::merge::MergeArgs;
+use crate::output::{OutputArgs, variants};
+use crate::parallel::harvest_parallel;
+use crate::rejects::{ErrorArgs, Rejects};
+use crate::sampling::{Sampling, SamplingArgs};
+use crate::sources::{base_name, detect_format, expand, files, open};
+use crate::validate::ValidateArgs;
+use clap::{Parser, Subcommand};
+use schema_harvester::{DocumentPath, EmbeddedJson, HarvestOptions, SchemaHypothesis, Selection};
+use serde_json::Value;
 use std::error::Error;
-use std::fs::File;
-use std::io::{self, Read};
+use std::fs;
+use std::io::{self, BufReader, ErrorKind, Read, Write};
+use std::path::Path;
+use std::process::ExitCode;
 
-fn main() -> Result<(), Box<dyn Error>> {
+mod diff;
+mod hypothesis;
+mod input;
+mod merge;
+mod output;
+mod parallel;
+mod rejects;
+mod sampling;
+mod sources;
+mod validate;
+
+/// number of lines per chunk when harvesting in parallel
+const CHUNK_SIZE: usize = 10_000;
+
+fn main() -> ExitCode {
     let args = Args::parse();
 
-    let reader: Box<dyn Read> = get_reader(args.file);
+    let result = match args.command {
+        Some(Command::Merge(merge_args)) => merge::merge(merge_args),
+        Some(Command::Validate(validate_args)) => validate::validate(validate_args),
+        Some(Command::Diff(diff_args)) => diff::diff(diff_args),
+        None => harvest(args),
+    };
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// print to stdout, a closed pipe (e.g. `| head`) is not an error
+fn print(output: &str) -> Result<(), Box<dyn Error>> {
+    match writeln!(io::stdout().lock(), "{output}") {
+        Err(e) if e.kind() == ErrorKind::BrokenPipe => Ok(()),
+        result => Ok(result?),
+    }
+}
+
+/// harvest a schema from the given paths or stdin
+fn harvest(args: Args) -> Result<(), Box<dyn Error>> {
+    let arguments: Vec<&str> = args
+        .file
+        .iter()
+        .chain(&args.paths)
+        .map(String::as_str)
+        .collect();
+    let mut rejects = Rejects::new(&args.errors)?;
+    if args.per_path {
+        harvest_per_path(&arguments, &args, &mut rejects)?;
+        return rejects.finish();
+    }
+
+    let mut current_hypothesis = match &args.resume {
+        Some(path) => read_hypothesis(path)?,
+        None => new_hypothesis("Sample"),
+    };
+    let mut sampling = Sampling::new(&args.sampling)?;
 
-    let deserializer = serde_json::Deserializer::from_reader(reader);
-    let iterator = deserializer.into_iter::<serde_json::Value>();
+    if arguments.is_empty() {
+        let input_format = args.input_format.unwrap_or_default();
+        let reader = Box::new(io::stdin());
+        harvest_reader(
+            &mut current_hypothesis,
+            reader,
+            input_format,
+            "stdin",
+            &args,
+            &mut rejects,
+            &mut sampling,
+        )?;
+    }
+    for argument in arguments {
+        for path in expand(argument)? {
+            harvest_path(
+                &mut current_hypothesis,
+                &path,
+                &args,
+                &mut rejects,
+                &mut sampling,
+            )?;
+        }
+    }
+    let (selection, options) = (args.selection(), args.harvest_options());
+    for document in sampling.finish() {
+        observe(&mut current_hypothesis, &document, &selection, &options);
+    }
+    rejects.finish()?;
 
-    let mut current_hypothesis = SchemaHypothesis::new(
+    print(&args.output.render(&current_hypothesis)?)
+}
+
+/// harvest each path into its own schema, written next to it
+fn harvest_per_path(
+    arguments: &[&str],
+    args: &Args,
+    rejects: &mut Rejects,
+) -> Result<(), Box<dyn Error>> {
+    if arguments.is_empty() {
+        return Err("--per-path requires at least one path".into());
+    }
+
+    for argument in arguments {
+        for path in expand(argument)? {
+            let base_name = base_name(&path)?;
+            let title = base_name
+                .file_name()
+                .map_or("Sample".into(), |name| name.to_string_lossy());
+            let mut hypothesis = new_hypothesis(&title);
+            let mut sampling = Sampling::new(&args.sampling)?;
+            harvest_path(&mut hypothesis, &path, args, rejects, &mut sampling)?;
+            let (selection, options) = (args.selection(), args.harvest_options());
+            for document in sampling.finish() {
+                observe(&mut hypothesis, &document, &selection, &options);
+            }
+
+            let mut target = base_name.into_os_string();
+            target.push(".");
+            target.push(args.output.extension());
+            fs::write(&target, args.output.render(&hypothesis)? + "\n")?;
+        }
+    }
+
+    Ok(())
+}
+
+fn new_hypothesis(title: &str) -> SchemaHypothesis {
+    SchemaHypothesis::new(
         "https:://github.com/elmarx/schema-harvester".to_string(),
-        "Sample".to_string(),
+        title.to_string(),
         "Auto-generated schema".to_string(),
-    );
+    )
+}
 
-    for json_document in iterator {
-        let new_hypo: NodeType = (&json_document?).into();
-        current_hypothesis = current_hypothesis.merge(new_hypo);
+/// harvest a file or all files of a directory
+fn harvest_path(
+    hypothesis: &mut SchemaHypothesis,
+    path: &Path,
+    args: &Args,
+    rejects: &mut Rejects,
+    sampling: &mut Sampling,
+) -> Result<(), Box<dyn Error>> {
+    for file in files(path, args.input_format.is_some())? {
+        if sampling.stopped() {
+            break;
+        }
+        let input_format = args
+            .input_format
+            .or_else(|| detect_format(&file))
+            .unwrap_or_default();
+        let source = file.display().to_string();
+        let reader = open(&file).map_err(|e| format!("{source}: {e}"))?;
+        harvest_reader(
+            hypothesis,
+            reader,
+            input_format,
+            &source,
+            args,
+            rejects,
+            sampling,
+        )?;
     }
 
-    let result = render_schema(&current_hypothesis);
+    Ok(())
+}
 
-    println!("{result}");
+/// harvest the documents of `source`, invalid ones are handled by `rejects`
+fn harvest_reader(
+    hypothesis: &mut SchemaHypothesis,
+    reader: Box<dyn Read>,
+    input_format: InputFormat,
+    source: &str,
+    args: &Args,
+    rejects: &mut Rejects,
+    sampling: &mut Sampling,
+) -> Result<(), Box<dyn Error>> {
+    let selection = args.selection();
+    let options = args.harvest_options();
+    if args.threads > 1 {
+        if input_format != InputFormat::Json {
+            return Err("--threads requires line-delimited JSON input".into());
+        }
+        let harvest = harvest_parallel(
+            BufReader::new(reader),
+            args.threads,
+            CHUNK_SIZE,
+            rejects.skips(),
+            &selection,
+            &options,
+            &args.sampling.options(),
+        )
+        .map_err(|e| format!("{source}: {e}"))?;
+        if let Some(root) = harvest.hypothesis.root {
+            hypothesis.merge_in_place_with(root, &options);
+        }
+        rejects.accepted += harvest.documents;
+        for invalid in harvest.invalid {
+            rejects.reject(source, invalid)?;
+        }
+        return Ok(());
+    }
+
+    let documents = if input_format == InputFormat::Json && rejects.skips() {
+        read_json_lines(reader)
+    } else {
+        read_documents(reader, input_format, &args.csv).map_err(|e| format!("{source}: {e}"))?
+    };
+    for document in documents {
+        if sampling.stopped() {
+            break;
+        }
+        match document {
+            Ok(document) => {
+                if let Some(document) = sampling.sample(document) {
+                    let changed = observe(hypothesis, &document, &selection, &options);
+                    sampling.observed(changed);
+                }
+                rejects.accepted += 1;
+            }
+            Err(invalid) => rejects.reject(source, invalid)?,
+        }
+    }
 
     Ok(())
 }
 
-fn get_reader(path: Option<String>) -> Box<dyn Read> {
-    if let Some(file_path) = path {
-        // Read from a file if the `--file` option is provided.
-        let file = File::open(file_path).expect("Failed to open file");
-        Box::new(file)
-    } else {
-        // Read from standard input if the `--file` option is not provided.
-        Box::new(io::stdin())
+/// harvest the selected values of a document, returns whether the hypothesis changed
+fn observe(
+    hypothesis: &mut SchemaHypothesis,
+    document: &Value,
+    selection: &Selection,
+    options: &HarvestOptions,
+) -> bool {
+    let mut changed = false;
+    for value in selection.select(document) {
+        changed |= hypothesis.observe_with(value, options);
     }
+    changed
 }
 
 #[derive(Parser, Debug)]
-#[clap(author, version, about, long_about = None)]
+#[clap(author, version, about, long_about = None, args_conflicts_with_subcommands = true)]
 struct Args {
+    #[clap(subcommand)]
+    command: Option<Command>,
+
+    /// files, directories (read recursively) or glob patterns to read documents from, defaults to stdin;
+    /// `.gz`, `.zst` and `.bz2` files are decompressed
+    paths: Vec<String>,
+
     #[clap(short, long)]
-    /// JSON file path
+    /// file to read documents from, same as passing it as path
     file: Option<String>,
+
+    #[clap(long, conflicts_with = "resume")]
+    /// harvest each path (or match of a glob pattern) into its own schema, written next to it, e.g. to
+    /// `orders.schema.json` for `orders.ndjson`
+    per_path: bool,
+
+    #[clap(short, long, value_enum)]
+    /// format of the documents, detected by the file extension by default (falling back to JSON)
+    input_format: Option<InputFormat>,
+
+    #[clap(flatten)]
+    csv: CsvArgs,
+
+    #[clap(flatten)]
+    errors: ErrorArgs,
+
+    #[clap(long)]
+    /// harvest only the values at this JSON Pointer (e.g. `/data/items`) or JSONPath (e.g. `$.data.items[*]`)
+    path: Option<DocumentPath>,
+
+    #[clap(long)]
+    /// harvest each element of (selected) arrays as a separate document
+    explode_arrays: bool,
+
+    #[clap(long, default_value_t, value_parser = variants::<EmbeddedJson>())]
+    /// harvest the structure of JSON documents embedded into strings (`json`), also base64-encoded ones
+    /// (`base64`), as `contentSchema`
+    embedded_json: EmbeddedJson,
+
+    #[clap(long)]
+    /// don't detect recursive structures (trees, linked lists, …), harvest each level of them separately
+    no_recursion: bool,
+
+    #[clap(long)]
+    /// hypothesis to resume harvesting from, as saved via `--output hypothesis`, or a JSON schema
+    resume: Option<String>,
+
+    #[clap(short, long, default_value_t = 1)]
+    /// number of threads to harvest with, more than one requires line-delimited JSON (one document per line)
+    threads: usize,
+
+    #[clap(flatten)]
+    sampling: SamplingArgs,
+
+    #[clap(flatten)]
+    output: OutputArgs,
+}
+
+impl Args {
+    fn selection(&self) -> Selection {
+        Selection {
+            path: self.path.clone().unwrap_or_default(),
+            explode_arrays: self.explode_arrays,
+        }
+    }
+
+    fn harvest_options(&self) -> HarvestOptions {
+        HarvestOptions {
+            embedded_json: self.embedded_json,
+            no_recursion: self.no_recursion,
+        }
+    }
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// merge saved hypotheses or JSON schemas into one schema
+    Merge(MergeArgs),
+    /// validate documents against a JSON schema or saved hypothesis, reporting the violations of each document
+    Validate(ValidateArgs),
+    /// list the structural changes between two saved hypotheses or JSON schemas
+    Diff(DiffArgs),
 }
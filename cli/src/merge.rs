@@ -0,0 +1,46 @@
+use crate::hypothesis::read_hypothesis;
+use crate::output::OutputArgs;
+use std::error::Error;
+
+#[derive(clap::Args, Debug)]
+pub struct MergeArgs {
+    #[clap(required = true)]
+    /// hypotheses saved via `--output hypothesis` or JSON schemas, the metadata of the first file wins
+    files: Vec<String>,
+
+    #[clap(long)]
+    /// id of the merged schema
+    id: Option<String>,
+
+    #[clap(long)]
+    /// title of the merged schema
+    title: Option<String>,
+
+    #[clap(long)]
+    /// description of the merged schema
+    description: Option<String>,
+
+    #[clap(flatten)]
+    output: OutputArgs,
+}
+
+/// merge several hypotheses (e.g. harvested from different partitions) into one
+pub fn merge(args: MergeArgs) -> Result<(), Box<dyn Error>> {
+    let (first, rest) = args.files.split_first().ok_or("no files to merge")?;
+    let mut merged = read_hypothesis(first)?;
+    for path in rest {
+        merged = merged.merge_hypothesis(read_hypothesis(path)?);
+    }
+
+    if let Some(id) = args.id {
+        merged.id = id;
+    }
+    if let Some(title) = args.title {
+        merged.title = title;
+    }
+    if let Some(description) = args.description {
+        merged.description = description;
+    }
+
+    crate::print(&args.output.render(&merged)?)
+}
@@ -0,0 +1,155 @@
+use clap::ValueEnum;
+use clap::builder::{PossibleValuesParser, TypedValueParser};
+use schema_harvester::{
+    AnyFallback, ArrowOptions, GoAnyType, GoOptions, JsonSchemaOptions, JvmOptions, NestedObjects,
+    SchemaHypothesis, SqlDialect, SqlOptions, render_arrow_json, render_arrow_text, render_go,
+    render_graphql, render_html, render_java, render_kotlin, render_markdown, render_pydantic,
+    render_schema_with_options, save_hypothesis,
+};
+use std::error::Error;
+use std::str::FromStr;
+use strum::VariantNames;
+
+#[derive(Clone, Copy, Debug, Default, ValueEnum)]
+pub enum OutputFormat {
+    /// JSON schema (draft-07)
+    #[default]
+    JsonSchema,
+    /// SQL `CREATE TABLE` statement
+    Sql,
+    /// Apache Arrow schema as JSON
+    ArrowJson,
+    /// Apache Arrow schema in arrow's text form
+    ArrowText,
+    /// GraphQL SDL
+    Graphql,
+    /// Python Pydantic (v2) models
+    Pydantic,
+    /// documentation as Markdown
+    Markdown,
+    /// documentation as self-contained HTML page
+    Html,
+    /// Go structs
+    Go,
+    /// Kotlin data classes with Jackson annotations
+    Kotlin,
+    /// Java records with Jackson annotations
+    Java,
+    /// the internal hypothesis, to resume harvesting later (see `--resume`)
+    Hypothesis,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct OutputArgs {
+    #[clap(short, long, value_enum, default_value_t)]
+    /// format to render the harvested schema in
+    output: OutputFormat,
+
+    #[clap(long)]
+    /// hoist objects that occur more than once into `definitions` for `--output json-schema`
+    extract_definitions: bool,
+
+    #[clap(long, default_value_t, value_parser = variants::<SqlDialect>())]
+    /// SQL dialect for `--output sql`
+    sql_dialect: SqlDialect,
+
+    #[clap(long, default_value_t, value_parser = variants::<NestedObjects>())]
+    /// how to map nested objects to columns for `--output sql`
+    sql_nested_objects: NestedObjects,
+
+    #[clap(long)]
+    /// table name for `--output sql`, defaults to the schema title
+    table_name: Option<String>,
+
+    #[clap(long, default_value_t, value_parser = variants::<AnyFallback>())]
+    /// how to represent values of mixed types for `--output arrow-json|arrow-text`
+    arrow_any_fallback: AnyFallback,
+
+    #[clap(long, default_value = "schema")]
+    /// package name for `--output go`
+    go_package: String,
+
+    #[clap(long, default_value_t, value_parser = variants::<GoAnyType>())]
+    /// type for values of mixed types for `--output go`
+    go_any_type: GoAnyType,
+
+    #[clap(long, default_value = "schema")]
+    /// package name for `--output kotlin|java`
+    jvm_package: String,
+}
+
+/// parse a strum-enum from core, offering its variants as possible values
+pub fn variants<T>() -> impl TypedValueParser<Value = T>
+where
+    T: FromStr + VariantNames + Clone + Send + Sync + 'static,
+    <T as FromStr>::Err: std::fmt::Debug,
+{
+    PossibleValuesParser::new(T::VARIANTS).map(|s| s.parse::<T>().unwrap())
+}
+
+impl OutputArgs {
+    pub fn render(&self, hypothesis: &SchemaHypothesis) -> Result<String, Box<dyn Error>> {
+        match self.output {
+            OutputFormat::JsonSchema => {
+                let options = JsonSchemaOptions {
+                    extract_definitions: self.extract_definitions,
+                };
+                Ok(render_schema_with_options(hypothesis, &options))
+            }
+            OutputFormat::Sql => {
+                let options = SqlOptions {
+                    dialect: self.sql_dialect,
+                    nested_objects: self.sql_nested_objects,
+                    table_name: self.table_name.clone(),
+                };
+                Ok(schema_harvester::render_sql(hypothesis, &options)?)
+            }
+            OutputFormat::ArrowJson => Ok(render_arrow_json(hypothesis, &self.arrow_options())?),
+            OutputFormat::ArrowText => Ok(render_arrow_text(hypothesis, &self.arrow_options())?),
+            OutputFormat::Graphql => Ok(render_graphql(hypothesis)),
+            OutputFormat::Pydantic => Ok(render_pydantic(hypothesis)),
+            OutputFormat::Markdown => Ok(render_markdown(hypothesis)),
+            OutputFormat::Html => Ok(render_html(hypothesis)),
+            OutputFormat::Go => {
+                let options = GoOptions {
+                    package: self.go_package.clone(),
+                    any_type: self.go_any_type,
+                };
+                Ok(render_go(hypothesis, &options))
+            }
+            OutputFormat::Kotlin => Ok(render_kotlin(hypothesis, &self.jvm_options())),
+            OutputFormat::Java => Ok(render_java(hypothesis, &self.jvm_options())),
+            OutputFormat::Hypothesis => Ok(save_hypothesis(hypothesis)),
+        }
+    }
+
+    /// file extension for the rendered schema
+    pub fn extension(&self) -> &'static str {
+        match self.output {
+            OutputFormat::JsonSchema => "schema.json",
+            OutputFormat::Sql => "sql",
+            OutputFormat::ArrowJson => "arrow.json",
+            OutputFormat::ArrowText => "arrow.txt",
+            OutputFormat::Graphql => "graphql",
+            OutputFormat::Pydantic => "py",
+            OutputFormat::Markdown => "md",
+            OutputFormat::Html => "html",
+            OutputFormat::Go => "go",
+            OutputFormat::Kotlin => "kt",
+            OutputFormat::Java => "java",
+            OutputFormat::Hypothesis => "hypothesis.json",
+        }
+    }
+
+    fn jvm_options(&self) -> JvmOptions {
+        JvmOptions {
+            package: self.jvm_package.clone(),
+        }
+    }
+
+    fn arrow_options(&self) -> ArrowOptions {
+        ArrowOptions {
+            any_fallback: self.arrow_any_fallback,
+        }
+    }
+}
@@ -0,0 +1,546 @@
+use crate::input::InvalidDocument;
+use schema_harvester::{HarvestOptions, Sampler, SamplingOptions, SchemaHypothesis, Selection};
+use serde_json::Value;
+use std::error::Error;
+use std::io::BufRead;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, mpsc};
+use std::thread;
+
+type Result<T> = std::result::Result<T, Box<dyn Error + Send + Sync>>;
+
+/// consecutive lines of the input
+struct Chunk {
+    index: usize,
+    /// byte offset of the first line
+    offset: u64,
+    lines: Vec<Vec<u8>>,
+}
+
+/// the result of harvesting (a chunk of) the input
+#[derive(Debug)]
+pub struct Harvest {
+    pub hypothesis: SchemaHypothesis,
+    /// number of valid documents
+    pub documents: usize,
+    /// invalid documents in input order, when skipping them
+    pub invalid: Vec<InvalidDocument>,
+}
+
+/// harvest line-delimited JSON on `threads` threads, in chunks of `chunk_size` lines
+///
+/// The partial hypotheses of the chunks are reduced pairwise in input order, so the result is the same as
+/// merging all documents sequentially. Merging hypotheses folds recursive structures just like merging
+/// documents does. The returned hypothesis has no metadata, merge it into the actual one.
+///
+/// Invalid documents fail the harvest, unless `skip_invalid` is set. Of the `sampling` options only the
+/// sample rate (and seed) apply, chunks are sampled independently.
+pub fn harvest_parallel(
+    reader: impl BufRead,
+    threads: usize,
+    chunk_size: usize,
+    skip_invalid: bool,
+    selection: &Selection,
+    options: &HarvestOptions,
+    sampling: &SamplingOptions,
+) -> Result<Harvest> {
+    let partials = process_chunks(reader, threads, chunk_size, |chunk| {
+        harvest_chunk(
+            chunk,
+            chunk_size,
+            skip_invalid,
+            selection,
+            options,
+            sampling,
+        )
+    })?;
+
+    let mut partials = partials;
+    partials.sort_by_key(|(index, _)| *index);
+
+    let mut documents = 0;
+    let mut invalid = vec![];
+    let mut hypotheses = vec![];
+    for (_, partial) in partials {
+        documents += partial.documents;
+        invalid.extend(partial.invalid);
+        hypotheses.push(partial.hypothesis);
+    }
+
+    Ok(Harvest {
+        hypothesis: reduce(hypotheses, options),
+        documents,
+        invalid,
+    })
+}
+
+/// process chunks of `chunk_size` lines with `work` on `threads` threads, results are in no particular order
+///
+/// After an error, the workers stop taking chunks and the input isn't read any further. Chunks taken before are
+/// finished, so the error returned is the one of the first failing chunk in input order.
+fn process_chunks<T: Send>(
+    reader: impl BufRead,
+    threads: usize,
+    chunk_size: usize,
+    work: impl Fn(Chunk) -> Result<T> + Sync,
+) -> Result<Vec<(usize, T)>> {
+    let (sender, receiver) = mpsc::sync_channel::<Chunk>(threads * 2);
+    // owned by the workers, so the reader stops once all of them stopped
+    let receiver = Arc::new(Mutex::new(receiver));
+    let abort = AtomicBool::new(false);
+
+    thread::scope(|scope| {
+        let workers: Vec<_> = (0..threads)
+            .map(|_| {
+                let receiver = Arc::clone(&receiver);
+                let (work, abort) = (&work, &abort);
+                scope.spawn(move || {
+                    let mut results = vec![];
+                    // chunks are taken in input order, checking before taking one keeps all earlier chunks
+                    while !abort.load(Ordering::Relaxed) {
+                        // release the lock before working on the chunk
+                        let next = receiver.lock().unwrap().recv();
+                        let Ok(chunk) = next else { break };
+                        let index = chunk.index;
+                        match work(chunk) {
+                            Ok(result) => results.push((index, result)),
+                            Err(e) => {
+                                abort.store(true, Ordering::Relaxed);
+                                return Err((index, e));
+                            }
+                        }
+                    }
+                    Ok(results)
+                })
+            })
+            .collect();
+        drop(receiver);
+
+        let read = read_chunks(reader, chunk_size, sender, &abort);
+
+        let mut results = vec![];
+        let mut first_error: Option<(usize, _)> = None;
+        for worker in workers {
+            match worker.join().unwrap() {
+                Ok(worker_results) => results.extend(worker_results),
+                Err((index, e)) => {
+                    if first_error.as_ref().is_none_or(|(first, _)| index < *first) {
+                        first_error = Some((index, e));
+                    }
+                }
+            }
+        }
+        if let Some((_, e)) = first_error {
+            return Err(e);
+        }
+        read?;
+        Ok(results)
+    })
+}
+
+/// send chunks of lines to the workers, until the input is exhausted or the workers stopped (due to an error)
+fn read_chunks(
+    mut reader: impl BufRead,
+    chunk_size: usize,
+    sender: mpsc::SyncSender<Chunk>,
+    abort: &AtomicBool,
+) -> Result<()> {
+    let mut offset = 0;
+    for index in 0.. {
+        let chunk_offset = offset;
+        let mut lines = Vec::with_capacity(chunk_size);
+        while lines.len() < chunk_size {
+            let mut line = vec![];
+            if reader.read_until(b'\n', &mut line)? == 0 {
+                break;
+            }
+            offset += line.len() as u64;
+            lines.push(line);
+        }
+
+        let chunk = Chunk {
+            index,
+            offset: chunk_offset,
+            lines,
+        };
+        if chunk.lines.is_empty() || abort.load(Ordering::Relaxed) || sender.send(chunk).is_err() {
+            break;
+        }
+    }
+    Ok(())
+}
+
+fn harvest_chunk(
+    chunk: Chunk,
+    chunk_size: usize,
+    skip_invalid: bool,
+    selection: &Selection,
+    options: &HarvestOptions,
+    sampling: &SamplingOptions,
+) -> Result<Harvest> {
+    let mut sampler = Sampler::new(&SamplingOptions {
+        sample_rate: sampling.sample_rate,
+        // a seed of its own for each chunk, so the sample doesn't depend on the number of threads
+        seed: sampling
+            .seed
+            .map(|seed| seed.wrapping_add(chunk.index as u64)),
+        ..SamplingOptions::default()
+    });
+    let mut harvest = Harvest {
+        hypothesis: empty(),
+        documents: 0,
+        invalid: vec![],
+    };
+
+    let mut offset = chunk.offset;
+    for (i, line) in chunk.lines.iter().enumerate() {
+        let line_offset = offset;
+        offset += line.len() as u64;
+
+        let line = line.trim_ascii_end();
+        if line.trim_ascii_start().is_empty() || !sampler.sample() {
+            continue;
+        }
+        let observed = if selection.is_identity() {
+            harvest
+                .hypothesis
+                .observe_slice_with(line, options)
+                .map(drop)
+        } else {
+            serde_json::from_slice::<Value>(line).map(|document| {
+                for value in selection.select(&document) {
+                    harvest.hypothesis.observe_with(value, options);
+                }
+            })
+        };
+        match observed {
+            Ok(()) => harvest.documents += 1,
+            Err(e) => {
+                let invalid = InvalidDocument {
+                    line: Some((chunk.index * chunk_size + i + 1) as u64),
+                    offset: Some(line_offset),
+                    raw: Some(line.to_vec()),
+                    error: e.into(),
+                };
+                if !skip_invalid {
+                    return Err(invalid.into());
+                }
+                harvest.invalid.push(invalid);
+            }
+        }
+    }
+    Ok(harvest)
+}
+
+/// merge neighbouring hypotheses until one is left
+fn reduce(mut partials: Vec<SchemaHypothesis>, options: &HarvestOptions) -> SchemaHypothesis {
+    while partials.len() > 1 {
+        let mut pairs = partials.into_iter();
+        let mut reduced = vec![];
+        while let Some(a) = pairs.next() {
+            reduced.push(match pairs.next() {
+                Some(b) => a.merge_hypothesis_with(b, options),
+                None => a,
+            });
+        }
+        partials = reduced;
+    }
+    partials.pop().unwrap_or_else(empty)
+}
+
+fn empty() -> SchemaHypothesis {
+    SchemaHypothesis::new(String::new(), String::new(), String::new())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{empty, harvest_parallel, process_chunks};
+    use schema_harvester::model::NodeType;
+    use schema_harvester::{HarvestOptions, SamplingOptions, Selection};
+    use serde_json::{Value, json};
+    use std::io::{BufRead, BufReader};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::mpsc;
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    fn documents() -> Vec<Value> {
+        (0..1000)
+            .map(|i| match i % 6 {
+                0 => json!({"id": i, "tags": ["a"], "price": 1.5}),
+                1 => json!({"id": i, "tags": [], "customer": {"name": "a", "since": "2000-01-01"}}),
+                2 => json!({"id": i.to_string(), "note": null}),
+                3 => json!([i, {"id": i}]),
+                4 => json!({"id": i, "customer": {"name": "b", "vip": true}}),
+                _ => json!({"id": i, "created": "2000-01-01T00:00:00Z"}),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_same_as_sequential() {
+        let documents = documents();
+        let input: String = documents.iter().map(|d| format!("{d}\n")).collect();
+        let sequential = documents.iter().fold(empty(), |hypothesis, document| {
+            hypothesis.merge(NodeType::from(document))
+        });
+
+        for (threads, chunk_size) in [(1, 1000), (2, 1), (4, 7), (3, 100)] {
+            let actual = harvest_parallel(
+                input.as_bytes(),
+                threads,
+                chunk_size,
+                false,
+                &Selection::default(),
+                &HarvestOptions::default(),
+                &SamplingOptions::default(),
+            )
+            .unwrap();
+
+            assert_eq!(actual.documents, documents.len());
+            assert_eq!(
+                actual.hypothesis, sequential,
+                "{threads} threads, chunks of {chunk_size}"
+            );
+        }
+    }
+
+    /// a comment thread, `depth` levels deep
+    fn thread(i: usize, depth: usize) -> Value {
+        let replies: Vec<_> = if depth == 0 {
+            vec![]
+        } else {
+            (0..1 + i % 2).map(|j| thread(i + j, depth - 1)).collect()
+        };
+        match i % 3 {
+            0 => json!({"id": i, "text": "a", "replies": replies}),
+            1 => json!({"id": i, "text": null, "replies": replies}),
+            _ => json!({"id": i.to_string(), "text": "a", "replies": replies}),
+        }
+    }
+
+    #[test]
+    fn test_recursive_same_as_sequential() {
+        // shallow threads first, so the recursion is found in later chunks only
+        let documents: Vec<_> = (0..200).map(|i| thread(i, i / 20)).collect();
+        let input: String = documents.iter().map(|d| format!("{d}\n")).collect();
+        let sequential = documents.iter().fold(empty(), |hypothesis, document| {
+            hypothesis.merge(NodeType::from(document))
+        });
+
+        for (threads, chunk_size) in [(1, 200), (2, 1), (4, 7), (3, 30)] {
+            let actual = harvest_parallel(
+                input.as_bytes(),
+                threads,
+                chunk_size,
+                false,
+                &Selection::default(),
+                &HarvestOptions::default(),
+                &SamplingOptions::default(),
+            )
+            .unwrap();
+
+            assert_eq!(
+                actual.hypothesis, sequential,
+                "{threads} threads, chunks of {chunk_size}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_invalid_line() {
+        let input = "{\"id\": 1}\n\n{\"id\": 2}\n{\"id\":\n";
+
+        let actual = harvest_parallel(
+            input.as_bytes(),
+            2,
+            2,
+            false,
+            &Selection::default(),
+            &HarvestOptions::default(),
+            &SamplingOptions::default(),
+        );
+
+        assert!(
+            actual
+                .unwrap_err()
+                .to_string()
+                .starts_with("line 4 (byte 21): ")
+        );
+    }
+
+    #[test]
+    fn test_skip_invalid() {
+        let input = "{\"id\": 1}\nnope\n{\"id\": 2}\n{\"id\":\n{\"id\": 3}\n";
+
+        let actual = harvest_parallel(
+            input.as_bytes(),
+            2,
+            2,
+            true,
+            &Selection::default(),
+            &HarvestOptions::default(),
+            &SamplingOptions::default(),
+        )
+        .unwrap();
+
+        assert_eq!(actual.documents, 3);
+        let invalid: Vec<_> = actual
+            .invalid
+            .iter()
+            .map(|invalid| (invalid.line, invalid.offset, invalid.raw.clone()))
+            .collect();
+        assert_eq!(
+            invalid,
+            vec![
+                (Some(2), Some(10), Some(b"nope".to_vec())),
+                (Some(4), Some(25), Some(b"{\"id\":".to_vec())),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_empty() {
+        let actual = harvest_parallel(
+            "".as_bytes(),
+            2,
+            10,
+            false,
+            &Selection::default(),
+            &HarvestOptions::default(),
+            &SamplingOptions::default(),
+        )
+        .unwrap();
+
+        assert_eq!(actual.hypothesis, empty());
+    }
+
+    #[test]
+    fn test_selection() {
+        let input = "{\"data\": [{\"id\": 1}, {\"id\": 2}]}\n{\"data\": []}\n{}\n";
+        let selection = Selection {
+            path: "/data".parse().unwrap(),
+            explode_arrays: true,
+        };
+
+        let actual = harvest_parallel(
+            input.as_bytes(),
+            2,
+            1,
+            false,
+            &selection,
+            &HarvestOptions::default(),
+            &SamplingOptions::default(),
+        )
+        .unwrap();
+
+        let expected = empty().merge(NodeType::from(&json!({"id": 1})));
+        assert_eq!(actual.hypothesis, expected);
+        assert_eq!(actual.documents, 3);
+    }
+
+    #[test]
+    fn test_sample_rate() {
+        let input: String = (0..1000).map(|i| format!("{{\"id\": {i}}}\n")).collect();
+        let sampling = SamplingOptions {
+            sample_rate: Some(0.1),
+            seed: Some(42),
+            ..SamplingOptions::default()
+        };
+        let harvest = |threads| {
+            harvest_parallel(
+                input.as_bytes(),
+                threads,
+                100,
+                false,
+                &Selection::default(),
+                &HarvestOptions::default(),
+                &sampling,
+            )
+            .unwrap()
+        };
+
+        let actual = harvest(2);
+
+        assert!(
+            (50..150).contains(&actual.documents),
+            "{}",
+            actual.documents
+        );
+        // the same sample regardless of the number of threads
+        assert_eq!(harvest(3).documents, actual.documents);
+    }
+
+    #[test]
+    fn test_chunks_in_parallel() {
+        let input = "{}\n{}\n";
+        let active = AtomicUsize::new(0);
+
+        // each chunk waits (for a while) for the other one to be worked on at the same time
+        let actual = process_chunks(input.as_bytes(), 2, 1, |_| {
+            active.fetch_add(1, Ordering::SeqCst);
+            let deadline = Instant::now() + Duration::from_secs(5);
+            while active.load(Ordering::SeqCst) < 2 && Instant::now() < deadline {
+                thread::yield_now();
+            }
+            Ok(active.load(Ordering::SeqCst))
+        })
+        .unwrap();
+
+        assert_eq!(actual.len(), 2);
+        assert!(actual.iter().all(|(_, active)| *active == 2), "{actual:?}");
+    }
+
+    #[test]
+    fn test_first_error_in_input_order() {
+        let input = "{}\n".repeat(4);
+
+        // the later chunk fails first
+        let actual = process_chunks(input.as_bytes(), 4, 1, |chunk| match chunk.index {
+            1 => {
+                thread::sleep(Duration::from_millis(100));
+                Err("chunk 1".into())
+            }
+            3 => Err("chunk 3".into()),
+            _ => Ok(()),
+        });
+
+        assert_eq!(actual.unwrap_err().to_string(), "chunk 1");
+    }
+
+    #[test]
+    fn test_stop_reading_after_error() {
+        let input = "{}\n".repeat(100_000);
+        let mut reader = BufReader::with_capacity(64, input.as_bytes());
+
+        let actual = process_chunks(&mut reader, 2, 1, |chunk| match chunk.index {
+            0 => Err("chunk 0".into()),
+            _ => Ok(()),
+        });
+
+        assert!(actual.is_err());
+        assert!(!reader.fill_buf().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_all_workers_failing() {
+        // many more invalid chunks than fit into the channel
+        let input = "nope\n".repeat(1000);
+        let (sender, receiver) = mpsc::channel();
+
+        thread::spawn(move || {
+            let actual = harvest_parallel(
+                input.as_bytes(),
+                2,
+                1,
+                false,
+                &Selection::default(),
+                &HarvestOptions::default(),
+                &SamplingOptions::default(),
+            );
+            sender.send(actual.is_err()).unwrap();
+        });
+
+        assert_eq!(receiver.recv_timeout(Duration::from_secs(10)), Ok(true));
+    }
+}
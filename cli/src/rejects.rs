@@ -0,0 +1,144 @@
+use crate::input::InvalidDocument;
+use clap::ValueEnum;
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum OnError {
+    /// abort on the first invalid document
+    #[default]
+    Fail,
+    /// skip invalid documents, reporting them on stderr
+    Skip,
+    /// skip invalid documents and write them to `--rejected`
+    Collect,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct ErrorArgs {
+    #[clap(long, value_enum, default_value_t)]
+    /// what to do with invalid documents, to skip them JSON is read line by line (one document per line)
+    on_error: OnError,
+
+    #[clap(long, required_if_eq("on_error", "collect"))]
+    /// file to write rejected documents to for `--on-error collect`, one per line (for line-delimited input)
+    rejected: Option<PathBuf>,
+}
+
+/// handles invalid documents according to `--on-error`, and counts them
+pub struct Rejects {
+    on_error: OnError,
+    file: Option<BufWriter<File>>,
+    pub accepted: usize,
+    pub rejected: usize,
+}
+
+impl Rejects {
+    pub fn new(args: &ErrorArgs) -> Result<Self, Box<dyn Error>> {
+        let file = match (&args.rejected, args.on_error) {
+            (Some(path), OnError::Collect) => Some(BufWriter::new(
+                File::create(path).map_err(|e| format!("{}: {e}", path.display()))?,
+            )),
+            _ => None,
+        };
+
+        Ok(Self {
+            on_error: args.on_error,
+            file,
+            accepted: 0,
+            rejected: 0,
+        })
+    }
+
+    /// whether invalid documents are skipped (rather than failing)
+    pub fn skips(&self) -> bool {
+        self.on_error != OnError::Fail
+    }
+
+    /// fail on an invalid document of `source`, or report (and collect) it
+    pub fn reject(
+        &mut self,
+        source: &str,
+        document: InvalidDocument,
+    ) -> Result<(), Box<dyn Error>> {
+        if !self.skips() {
+            return Err(format!("{source}: {document}").into());
+        }
+
+        eprintln!("skipped {source}: {document}");
+        self.rejected += 1;
+        if let (Some(file), Some(raw)) = (&mut self.file, &document.raw) {
+            file.write_all(raw)?;
+            file.write_all(b"\n")?;
+        }
+        Ok(())
+    }
+
+    /// print a summary when skipping invalid documents
+    pub fn finish(mut self) -> Result<(), Box<dyn Error>> {
+        if let Some(file) = &mut self.file {
+            file.flush()?;
+        }
+        if self.skips() {
+            eprintln!(
+                "{} documents harvested, {} invalid documents skipped",
+                self.accepted, self.rejected
+            );
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ErrorArgs, OnError, Rejects};
+    use crate::input::InvalidDocument;
+    use std::fs;
+
+    fn invalid(line: u64, raw: &str) -> InvalidDocument {
+        InvalidDocument {
+            line: Some(line),
+            offset: Some(0),
+            raw: Some(raw.as_bytes().to_vec()),
+            error: "invalid".into(),
+        }
+    }
+
+    #[test]
+    fn test_fail() {
+        let mut rejects = Rejects::new(&ErrorArgs {
+            on_error: OnError::Fail,
+            rejected: None,
+        })
+        .unwrap();
+
+        let actual = rejects.reject("a.json", invalid(3, "nope"));
+
+        assert_eq!(
+            actual.unwrap_err().to_string(),
+            "a.json: line 3 (byte 0): invalid"
+        );
+    }
+
+    #[test]
+    fn test_collect() {
+        let path = std::env::temp_dir().join(format!(
+            "schema-harvester-rejected-{}.jsonl",
+            std::process::id()
+        ));
+        let mut rejects = Rejects::new(&ErrorArgs {
+            on_error: OnError::Collect,
+            rejected: Some(path.clone()),
+        })
+        .unwrap();
+
+        rejects.reject("a.json", invalid(3, "nope")).unwrap();
+        rejects.reject("b.json", invalid(1, "{\"id\":")).unwrap();
+        assert_eq!(rejects.rejected, 2);
+        rejects.finish().unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "nope\n{\"id\":\n");
+    }
+}
@@ -0,0 +1,157 @@
+use schema_harvester::{Reservoir, Sampler, SamplingOptions, parse_duration};
+use serde_json::Value;
+use std::error::Error;
+use std::time::Duration;
+
+#[derive(clap::Args, Debug)]
+pub struct SamplingArgs {
+    #[clap(long)]
+    /// fraction of documents to harvest, e.g. `0.01` for one in a hundred (on average)
+    sample_rate: Option<f64>,
+
+    #[clap(long, conflicts_with = "threads")]
+    /// harvest a uniform random sample of this many documents (kept in memory until the end)
+    reservoir: Option<usize>,
+
+    #[clap(long, conflicts_with = "threads")]
+    /// stop after harvesting this many (sampled) documents
+    max_documents: Option<u64>,
+
+    #[clap(long, value_parser = parse_duration, conflicts_with = "threads")]
+    /// stop harvesting after this time, e.g. `90s`, `15m` or `2h`
+    max_duration: Option<Duration>,
+
+    #[clap(long, value_name = "DOCUMENTS", conflicts_with_all = ["threads", "reservoir"])]
+    /// stop once this many consecutive documents didn't change the schema
+    stop_when_stable: Option<u64>,
+
+    #[clap(long)]
+    /// seed for sampling, to harvest the same sample again
+    seed: Option<u64>,
+}
+
+impl SamplingArgs {
+    pub fn options(&self) -> SamplingOptions {
+        SamplingOptions {
+            sample_rate: self.sample_rate,
+            reservoir: self.reservoir,
+            max_documents: self.max_documents,
+            max_duration: self.max_duration,
+            stop_when_stable: self.stop_when_stable,
+            seed: self.seed,
+        }
+    }
+}
+
+/// samples the documents of a harvest according to `SamplingArgs`
+pub struct Sampling {
+    sampler: Sampler,
+    reservoir: Option<Reservoir<Value>>,
+}
+
+impl Sampling {
+    pub fn new(args: &SamplingArgs) -> Result<Self, Box<dyn Error>> {
+        let options = args.options();
+        options.validate()?;
+
+        Ok(Self {
+            sampler: Sampler::new(&options),
+            reservoir: options
+                .reservoir
+                .map(|capacity| Reservoir::new(capacity, options.seed)),
+        })
+    }
+
+    /// whether harvesting stopped early, so the rest of the input can be skipped
+    pub fn stopped(&self) -> bool {
+        self.sampler.stopped().is_some()
+    }
+
+    /// the document to harvest right away, if it's sampled and not kept in the reservoir
+    pub fn sample(&mut self, document: Value) -> Option<Value> {
+        if !self.sampler.sample() {
+            return None;
+        }
+        match &mut self.reservoir {
+            Some(reservoir) => {
+                reservoir.offer(document);
+                None
+            }
+            None => Some(document),
+        }
+    }
+
+    /// record whether harvesting a sampled document changed the hypothesis
+    pub fn observed(&mut self, changed: bool) {
+        self.sampler.observed(changed);
+    }
+
+    /// the documents of the reservoir to harvest at last, reports if harvesting stopped early
+    pub fn finish(self) -> Vec<Value> {
+        if let Some(stop) = self.sampler.stopped() {
+            eprintln!("stopped harvesting early: {stop}");
+        }
+        self.reservoir
+            .map(Reservoir::into_items)
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Sampling, SamplingArgs};
+    use serde_json::json;
+
+    fn args() -> SamplingArgs {
+        SamplingArgs {
+            sample_rate: None,
+            reservoir: None,
+            max_documents: None,
+            max_duration: None,
+            stop_when_stable: None,
+            seed: Some(7),
+        }
+    }
+
+    #[test]
+    fn test_max_documents() {
+        let mut sampling = Sampling::new(&SamplingArgs {
+            max_documents: Some(2),
+            ..args()
+        })
+        .unwrap();
+
+        let sampled: Vec<_> = (0..5)
+            .filter_map(|i| sampling.sample(json!({"id": i})))
+            .collect();
+
+        assert_eq!(sampled, vec![json!({"id": 0}), json!({"id": 1})]);
+        assert!(sampling.stopped());
+        assert!(sampling.finish().is_empty());
+    }
+
+    #[test]
+    fn test_reservoir() {
+        let mut sampling = Sampling::new(&SamplingArgs {
+            reservoir: Some(3),
+            ..args()
+        })
+        .unwrap();
+
+        for i in 0..100 {
+            assert_eq!(sampling.sample(json!({"id": i})), None);
+        }
+
+        assert_eq!(sampling.finish().len(), 3);
+    }
+
+    #[test]
+    fn test_invalid() {
+        let actual = Sampling::new(&SamplingArgs {
+            sample_rate: Some(2.0),
+            ..args()
+        });
+
+        assert!(actual.is_err());
+    }
+}
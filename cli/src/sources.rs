@@ -0,0 +1,221 @@
+use crate::input::InputFormat;
+use bzip2::read::MultiBzDecoder;
+use flate2::read::MultiGzDecoder;
+use std::error::Error;
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+/// compression of a file, detected by its extension
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Compression {
+    Gzip,
+    Zstd,
+    Bzip2,
+}
+
+impl Compression {
+    pub fn detect(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "gz" | "gzip" => Some(Self::Gzip),
+            "zst" | "zstd" => Some(Self::Zstd),
+            "bz2" => Some(Self::Bzip2),
+            _ => None,
+        }
+    }
+}
+
+/// the path without a compression extension, e.g. `dump.ndjson` for `dump.ndjson.gz`
+fn decompressed(path: &Path) -> PathBuf {
+    match Compression::detect(path) {
+        Some(_) => path.with_extension(""),
+        None => path.to_path_buf(),
+    }
+}
+
+/// detect the format of a (possibly compressed) file by its extension
+pub fn detect_format(path: &Path) -> Option<InputFormat> {
+    InputFormat::detect(&decompressed(path))
+}
+
+/// open a file, decompressed by its extension
+pub fn open(path: &Path) -> Result<Box<dyn Read>, Box<dyn Error>> {
+    let file = File::open(path)?;
+    Ok(match Compression::detect(path) {
+        None => Box::new(file),
+        Some(Compression::Gzip) => Box::new(MultiGzDecoder::new(file)),
+        Some(Compression::Zstd) => Box::new(zstd::Decoder::new(file)?),
+        Some(Compression::Bzip2) => Box::new(MultiBzDecoder::new(file)),
+    })
+}
+
+/// the paths an argument stands for: the file or directory itself, or the matches of a glob pattern
+pub fn expand(argument: &str) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    let path = Path::new(argument);
+    if path.exists() {
+        return Ok(vec![path.to_path_buf()]);
+    }
+
+    let matches = glob::glob(argument)?.collect::<Result<Vec<_>, _>>()?;
+    if matches.is_empty() {
+        return Err(format!("{argument}: no such file or directory").into());
+    }
+    Ok(matches)
+}
+
+/// the files to harvest for a path, directories are walked recursively (in lexical order)
+///
+/// Within directories hidden entries and symbolic links to directories (which may form loops) are skipped, as
+/// well as files of unknown format unless `all_files` is set (i.e. the format is given explicitly).
+pub fn files(path: &Path, all_files: bool) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    if !path.is_dir() {
+        return Ok(vec![path.to_path_buf()]);
+    }
+
+    let mut entries = fs::read_dir(path)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<Vec<_>, _>>()?;
+    entries.sort();
+
+    let mut files = vec![];
+    for entry in entries {
+        let hidden = entry
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| name.starts_with('.'));
+        if hidden {
+            continue;
+        }
+        if entry.is_dir() {
+            if fs::symlink_metadata(&entry)?.is_symlink() {
+                continue;
+            }
+            files.extend(self::files(&entry, all_files)?);
+        } else if all_files || detect_format(&entry).is_some() {
+            files.push(entry);
+        }
+    }
+    Ok(files)
+}
+
+/// the path without its extensions, e.g. `data/orders` for `data/orders.ndjson.gz` (directories are kept as is)
+pub fn base_name(path: &Path) -> Result<PathBuf, Box<dyn Error>> {
+    if path.is_dir() {
+        // canonicalize to get a name for e.g. `.`
+        Ok(fs::canonicalize(path)?)
+    } else {
+        Ok(decompressed(path).with_extension(""))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Compression, base_name, detect_format, expand, files, open};
+    use crate::input::InputFormat;
+    use std::fs;
+    use std::io::{Read, Write};
+    use std::path::{Path, PathBuf};
+
+    /// a fresh directory for a test
+    fn test_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("schema-harvester-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_detect() {
+        assert_eq!(
+            Compression::detect(Path::new("dump.ndjson.gz")),
+            Some(Compression::Gzip)
+        );
+        assert_eq!(
+            detect_format(Path::new("dump.ndjson.gz")),
+            Some(InputFormat::Json)
+        );
+        assert_eq!(
+            detect_format(Path::new("values.yaml.zst")),
+            Some(InputFormat::Yaml)
+        );
+        assert_eq!(detect_format(Path::new("dump.bz2")), None);
+        assert_eq!(
+            base_name(Path::new("data/orders.csv.bz2")).unwrap(),
+            PathBuf::from("data/orders")
+        );
+    }
+
+    #[test]
+    fn test_open_compressed() {
+        let dir = test_dir("compressed");
+        let content = b"{\"id\": 1}\n";
+
+        let mut gzip = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        gzip.write_all(content).unwrap();
+        fs::write(dir.join("a.json.gz"), gzip.finish().unwrap()).unwrap();
+        fs::write(
+            dir.join("a.json.zst"),
+            zstd::encode_all(&content[..], 0).unwrap(),
+        )
+        .unwrap();
+        let mut bzip2 = bzip2::write::BzEncoder::new(Vec::new(), bzip2::Compression::default());
+        bzip2.write_all(content).unwrap();
+        fs::write(dir.join("a.json.bz2"), bzip2.finish().unwrap()).unwrap();
+
+        for name in ["a.json.gz", "a.json.zst", "a.json.bz2"] {
+            let mut actual = vec![];
+            open(&dir.join(name))
+                .unwrap()
+                .read_to_end(&mut actual)
+                .unwrap();
+            assert_eq!(actual, content, "{name}");
+        }
+    }
+
+    #[test]
+    fn test_files_and_globs() {
+        let dir = test_dir("walk");
+        fs::create_dir_all(dir.join("b/c")).unwrap();
+        fs::create_dir_all(dir.join(".git")).unwrap();
+        for name in [
+            "a.json",
+            "b/c/d.yaml",
+            "b/README",
+            "b/e.ndjson.gz",
+            ".git/f.json",
+        ] {
+            fs::write(dir.join(name), "").unwrap();
+        }
+
+        assert_eq!(
+            files(&dir, false).unwrap(),
+            vec![
+                dir.join("a.json"),
+                dir.join("b/c/d.yaml"),
+                dir.join("b/e.ndjson.gz")
+            ]
+        );
+        assert_eq!(files(&dir.join("b"), true).unwrap().len(), 3);
+
+        let pattern = format!("{}/b/**/*.yaml", dir.display());
+        assert_eq!(expand(&pattern).unwrap(), vec![dir.join("b/c/d.yaml")]);
+        assert!(expand(&format!("{}/*.csv", dir.display())).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_skip_linked_directories() {
+        let dir = test_dir("links");
+        fs::create_dir_all(dir.join("b")).unwrap();
+        fs::write(dir.join("b/c.json"), "").unwrap();
+        std::os::unix::fs::symlink(&dir, dir.join("b/loop")).unwrap();
+        std::os::unix::fs::symlink(dir.join("b/c.json"), dir.join("d.json")).unwrap();
+
+        assert_eq!(
+            files(&dir, false).unwrap(),
+            vec![dir.join("b/c.json"), dir.join("d.json")]
+        );
+    }
+}
@@ -0,0 +1,240 @@
+use crate::hypothesis::read_schema;
+use crate::input::{CsvArgs, InputFormat, read_documents, read_json};
+use crate::sources::{detect_format, expand, files, open};
+use jsonschema::Validator;
+use serde_json::Value;
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+use std::io::{self, Read};
+use std::path::PathBuf;
+
+#[derive(clap::Args, Debug)]
+pub struct ValidateArgs {
+    #[clap(short, long)]
+    /// JSON schema (harvested or hand-written) or hypothesis saved via `--output hypothesis` to validate against
+    schema: String,
+
+    /// files, directories (read recursively) or glob patterns to read documents from, defaults to stdin
+    paths: Vec<String>,
+
+    #[clap(short, long, value_enum)]
+    /// format of the documents, detected by the file extension by default (falling back to JSON)
+    input_format: Option<InputFormat>,
+
+    #[clap(flatten)]
+    csv: CsvArgs,
+
+    #[clap(long)]
+    /// stop at the first invalid document
+    fail_fast: bool,
+}
+
+/// a violation of the schema within a document
+#[derive(Debug, PartialEq, Eq)]
+struct Violation {
+    /// JSON Pointer to the violating value
+    path: String,
+    message: String,
+}
+
+#[derive(Debug, Default)]
+struct Summary {
+    valid: usize,
+    invalid: usize,
+}
+
+impl Display for Violation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self.path.as_str() {
+            "" => write!(f, "(root): {}", self.message),
+            path => write!(f, "{path}: {}", self.message),
+        }
+    }
+}
+
+/// validate documents against a schema, printing the violations of each invalid document and a summary
+pub fn validate(args: ValidateArgs) -> Result<(), Box<dyn Error>> {
+    let schema = read_schema(&args.schema)?;
+    let validator = jsonschema::validator_for(&schema)
+        .map_err(|e| format!("{}: invalid schema: {e}", args.schema))?;
+
+    let mut summary = Summary::default();
+    if args.paths.is_empty() {
+        let input_format = args.input_format.unwrap_or_default();
+        validate_reader(
+            &validator,
+            Box::new(io::stdin()),
+            input_format,
+            "stdin",
+            &args,
+            &mut summary,
+        )?;
+    }
+
+    let mut sources: Vec<PathBuf> = vec![];
+    for argument in &args.paths {
+        for path in expand(argument)? {
+            sources.extend(files(&path, args.input_format.is_some())?);
+        }
+    }
+    for file in sources {
+        if args.fail_fast && summary.invalid > 0 {
+            break;
+        }
+        let input_format = args
+            .input_format
+            .or_else(|| detect_format(&file))
+            .unwrap_or_default();
+        let source = file.display().to_string();
+        let reader = open(&file).map_err(|e| format!("{source}: {e}"))?;
+        validate_reader(
+            &validator,
+            reader,
+            input_format,
+            &source,
+            &args,
+            &mut summary,
+        )?;
+    }
+
+    crate::print(&format!(
+        "{} documents, {} valid, {} invalid",
+        summary.valid + summary.invalid,
+        summary.valid,
+        summary.invalid
+    ))?;
+    match summary.invalid {
+        0 => Ok(()),
+        invalid => Err(format!("{invalid} invalid documents").into()),
+    }
+}
+
+fn validate_reader(
+    validator: &Validator,
+    reader: Box<dyn Read>,
+    input_format: InputFormat,
+    source: &str,
+    args: &ValidateArgs,
+    summary: &mut Summary,
+) -> Result<(), Box<dyn Error>> {
+    let documents = match input_format {
+        InputFormat::Json => read_json(reader),
+        input_format => read_documents(reader, input_format, &args.csv),
+    }
+    .map_err(|e| format!("{source}: {e}"))?;
+
+    for (index, document) in documents.enumerate() {
+        match document {
+            Ok(document) => {
+                let violations = violations(validator, &document);
+                if violations.is_empty() {
+                    summary.valid += 1;
+                    continue;
+                }
+                for violation in violations {
+                    crate::print(&format!("{source}: document {}: {violation}", index + 1))?;
+                }
+            }
+            Err(invalid) => crate::print(&format!("{source}: {invalid}"))?,
+        }
+
+        summary.invalid += 1;
+        if args.fail_fast {
+            break;
+        }
+    }
+    Ok(())
+}
+
+fn violations(validator: &Validator, document: &Value) -> Vec<Violation> {
+    validator
+        .iter_errors(document)
+        .map(|error| Violation {
+            path: error.instance_path.to_string(),
+            message: error.to_string(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Summary, ValidateArgs, Violation, validate_reader, violations};
+    use crate::input::InputFormat;
+    use clap::Parser;
+    use schema_harvester::{SchemaHypothesis, render_schema};
+    use serde_json::{Value, json};
+
+    #[derive(Parser)]
+    struct Command {
+        #[clap(flatten)]
+        validate: ValidateArgs,
+    }
+
+    #[test]
+    fn test_violations() {
+        let schema = json!({
+            "type": "object",
+            "required": ["id"],
+            "properties": {
+                "id": {"type": "integer"},
+                "tags": {"type": "array", "items": {"type": "string"}}
+            }
+        });
+        let validator = jsonschema::validator_for(&schema).unwrap();
+
+        let actual = violations(&validator, &json!({"tags": ["a", 1]}));
+
+        assert_eq!(
+            actual,
+            vec![
+                Violation {
+                    path: "".to_string(),
+                    message: "\"id\" is a required property".to_string()
+                },
+                Violation {
+                    path: "/tags/1".to_string(),
+                    message: "1 is not of type \"string\"".to_string()
+                },
+            ]
+        );
+        assert_eq!(
+            actual[0].to_string(),
+            "(root): \"id\" is a required property"
+        );
+        assert!(violations(&validator, &json!({"id": 1, "tags": []})).is_empty());
+    }
+
+    #[test]
+    fn test_harvested_schema() {
+        let hypothesis = SchemaHypothesis::new("id".into(), "order".into(), String::new())
+            .merge((&json!({"id": 1, "at": "2000-01-01"})).into());
+        let schema: Value = serde_json::from_str(&render_schema(&hypothesis)).unwrap();
+        let validator = jsonschema::validator_for(&schema).unwrap();
+
+        let actual = violations(&validator, &json!({"id": "1", "at": "2000-01-01"}));
+
+        assert_eq!(actual.len(), 1);
+        assert_eq!(actual[0].path, "/id");
+    }
+
+    #[test]
+    fn test_continue_after_invalid_line() {
+        let args = Command::parse_from(["validate", "--schema", "schema.json"]).validate;
+        let schema = json!({"type": "object", "properties": {"a": {"type": "integer"}}});
+        let validator = jsonschema::validator_for(&schema).unwrap();
+        let input = "{\"a\":1}\n{\"a\":\n{\"a\":\"x\"}\n{\"a\":2}\n";
+        let mut summary = Summary::default();
+
+        validate_reader(
+            &validator,
+            Box::new(input.as_bytes()),
+            InputFormat::Json,
+            "stdin",
+            &args,
+            &mut summary,
+        )
+        .unwrap();
+
+        assert_eq!((summary.valid, summary.invalid), (2, 2));
+    }
+}
@@ -0,0 +1,131 @@
+//! merge performance on realistic, slowly converging documents
+//!
+//! Run with `cargo bench -p schema-harvester`. `observe/clone-merge-compare` is how the service used to
+//! detect changes, compare it with `observe/merge-in-place`. The `document` group compares generating a
+//! [`NodeType`] per document with observing documents directly. The `recursive` group observes trees, whose
+//! hypothesis contains references (i.e. is folded after each change).
+
+use criterion::{BatchSize, Criterion, black_box, criterion_group, criterion_main};
+use schema_harvester::SchemaHypothesis;
+use schema_harvester::model::NodeType;
+use serde_json::{Value, json};
+
+fn document(i: usize) -> Value {
+    json!({
+        "id": i,
+        "created": "2000-01-01T00:00:00Z",
+        "customer": {"id": format!("c{}", i % 100), "name": "a", "vip": i % 3 == 0},
+        "items": (0..i % 5).map(|j| json!({"sku": format!("s{j}"), "quantity": j, "price": 1.5})).collect::<Vec<_>>(),
+        "tags": if i % 2 == 0 { json!(["a", "b"]) } else { json!(null) },
+        "shipping": {"address": {"street": "Main", "city": "A", "zip": i}, "express": i % 7 == 0},
+    })
+}
+
+/// a comment thread, `depth` levels deep
+fn thread(i: usize, depth: usize) -> Value {
+    let replies = if depth == 0 {
+        vec![]
+    } else {
+        (0..1 + i % 3).map(|j| thread(i + j, depth - 1)).collect()
+    };
+    json!({"id": i, "author": {"name": "a", "vip": i % 3 == 0}, "text": "a", "replies": replies})
+}
+
+fn hypothesis(documents: &[NodeType]) -> SchemaHypothesis {
+    documents.iter().fold(
+        SchemaHypothesis::new("id".to_string(), "order".to_string(), String::new()),
+        |hypothesis, document| hypothesis.merge(document.clone()),
+    )
+}
+
+fn merge(c: &mut Criterion) {
+    let documents: Vec<NodeType> = (0..1000).map(|i| NodeType::from(&document(i))).collect();
+
+    c.bench_function("merge/1000 documents", |b| {
+        b.iter_batched(
+            || documents.clone(),
+            |documents| {
+                documents.into_iter().fold(
+                    SchemaHypothesis::new("id".to_string(), "order".to_string(), String::new()),
+                    SchemaHypothesis::merge,
+                )
+            },
+            BatchSize::SmallInput,
+        )
+    });
+
+    // the common case for the service: the hypothesis is stable, documents don't change it
+    let stable = hypothesis(&documents);
+    let document = NodeType::from(&self::document(1001));
+
+    let mut group = c.benchmark_group("observe");
+    group.bench_function("clone-merge-compare", |b| {
+        b.iter_batched(
+            || document.clone(),
+            |document| {
+                let merged = stable.clone().merge(document);
+                black_box(merged != stable)
+            },
+            BatchSize::SmallInput,
+        )
+    });
+    group.bench_function("merge-in-place", |b| {
+        let mut hypothesis = stable.clone();
+        b.iter_batched(
+            || document.clone(),
+            |document| black_box(hypothesis.merge_in_place(document)),
+            BatchSize::SmallInput,
+        )
+    });
+    group.finish();
+
+    // the whole way from a document to the hypothesis
+    let value = self::document(1001);
+    let bytes = serde_json::to_vec(&value).unwrap();
+
+    let mut group = c.benchmark_group("document");
+    group.bench_function("from-and-merge-in-place", |b| {
+        let mut hypothesis = stable.clone();
+        b.iter(|| black_box(hypothesis.merge_in_place(NodeType::from(&value))))
+    });
+    group.bench_function("observe", |b| {
+        let mut hypothesis = stable.clone();
+        b.iter(|| black_box(hypothesis.observe(&value)))
+    });
+    group.bench_function("parse-from-and-merge-in-place", |b| {
+        let mut hypothesis = stable.clone();
+        b.iter(|| {
+            let value: Value = serde_json::from_slice(&bytes).unwrap();
+            black_box(hypothesis.merge_in_place(NodeType::from(&value)))
+        })
+    });
+    group.bench_function("observe-slice", |b| {
+        let mut hypothesis = stable.clone();
+        b.iter(|| black_box(hypothesis.observe_slice(&bytes).unwrap()))
+    });
+    group.finish();
+}
+
+fn recursive(c: &mut Criterion) {
+    let mut stable = SchemaHypothesis::new("id".to_string(), "thread".to_string(), String::new());
+    for i in 0..100 {
+        stable.observe(&thread(i, 1 + i % 4));
+    }
+    // deeper than all documents before, so it changes the hypothesis until folded
+    let deeper = serde_json::to_vec(&thread(1001, 6)).unwrap();
+    let known = serde_json::to_vec(&thread(1001, 2)).unwrap();
+
+    let mut group = c.benchmark_group("recursive");
+    group.bench_function("observe-slice", |b| {
+        let mut hypothesis = stable.clone();
+        b.iter(|| black_box(hypothesis.observe_slice(&known).unwrap()))
+    });
+    group.bench_function("observe-slice-deeper", |b| {
+        let mut hypothesis = stable.clone();
+        b.iter(|| black_box(hypothesis.observe_slice(&deeper).unwrap()))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, merge, recursive);
+criterion_main!(benches);
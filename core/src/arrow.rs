@@ -0,0 +1,269 @@
+//! derive an [Apache Arrow](https://arrow.apache.org/) schema (e.g. to write Parquet files) from a [`SchemaHypothesis`]
+
+use crate::SchemaHypothesis;
+use crate::error::RenderError;
+use crate::model::{AnyNode, NodeType, ObjectProperty, StringFormat};
+use arrow_schema::{DataType, Field, Fields, Schema, TimeUnit, UnionFields, UnionMode};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use strum_macros::{Display, EnumString, VariantNames};
+
+/// how to represent [`AnyNode`]s, i.e. values of different types, in arrow
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default, Display, EnumString, VariantNames)]
+#[strum(serialize_all = "kebab-case")]
+pub enum AnyFallback {
+    /// JSON-encoded values in a `Utf8` column, annotated with the canonical `arrow.json` extension type
+    #[default]
+    Json,
+    /// like `Json`, but `LargeUtf8`
+    LargeJson,
+    /// a dense union with one child per type
+    Union,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct ArrowOptions {
+    pub any_fallback: AnyFallback,
+}
+
+/// derive an arrow schema with one field per property of the root object
+///
+/// # Errors
+///
+/// if the hypothesis is empty or its root is not an object
+pub fn arrow_schema(
+    schema: &SchemaHypothesis,
+    options: &ArrowOptions,
+) -> Result<Schema, RenderError> {
+    let root = schema.root.as_ref().ok_or(RenderError::EmptyHypothesis)?;
+    let NodeType::Object(root) = root else {
+        return Err(RenderError::RootNotAnObject);
+    };
+
+    Ok(Schema::new(fields(&root.properties, options)))
+}
+
+/// render the arrow schema as JSON
+///
+/// # Errors
+///
+/// if the hypothesis is empty or its root is not an object
+#[allow(clippy::missing_panics_doc)]
+pub fn render_arrow_json(
+    schema: &SchemaHypothesis,
+    options: &ArrowOptions,
+) -> Result<String, RenderError> {
+    let schema = arrow_schema(schema, options)?;
+
+    Ok(serde_json::to_string_pretty(&schema).unwrap())
+}
+
+/// render the arrow schema in arrow's textual form, one field per line
+///
+/// # Errors
+///
+/// if the hypothesis is empty or its root is not an object
+pub fn render_arrow_text(
+    schema: &SchemaHypothesis,
+    options: &ArrowOptions,
+) -> Result<String, RenderError> {
+    let schema = arrow_schema(schema, options)?;
+
+    Ok(schema
+        .fields()
+        .iter()
+        .map(|field| {
+            let nullability = if field.is_nullable() { "" } else { "non-null " };
+            format!("{}: {nullability}{}", field.name(), field.data_type())
+        })
+        .collect::<Vec<_>>()
+        .join("\n"))
+}
+
+fn fields(properties: &BTreeMap<String, ObjectProperty>, options: &ArrowOptions) -> Fields {
+    properties
+        .iter()
+        .map(|(key, property)| field(key, &property.node_type, property.required, options))
+        .collect()
+}
+
+fn field(name: &str, node_type: &NodeType, required: bool, options: &ArrowOptions) -> Field {
+    let (node_type, nullable) = node_type.split_null();
+    let nullable = nullable || !required;
+
+    match node_type {
+        None => Field::new(name, DataType::Null, true),
+        Some(NodeType::Any(any)) => any_field(name, any, nullable, options),
+        // arrow has no recursive types
+        Some(NodeType::Recursive(_)) => json_field(name, nullable, options),
+        Some(node_type) => Field::new(name, data_type(node_type, options), nullable),
+    }
+}
+
+fn data_type(node_type: &NodeType, options: &ArrowOptions) -> DataType {
+    match node_type {
+        NodeType::Null => DataType::Null,
+        NodeType::Boolean => DataType::Boolean,
+        NodeType::Integer(_) => DataType::Int64,
+        NodeType::Number(_) => DataType::Float64,
+        NodeType::String(s) => match s.format {
+            Some(StringFormat::DateTime) => {
+                DataType::Timestamp(TimeUnit::Microsecond, Some("UTC".into()))
+            }
+            Some(StringFormat::Date) => DataType::Date32,
+            Some(StringFormat::Time) => DataType::Time64(TimeUnit::Microsecond),
+            Some(StringFormat::Uuid) | None => DataType::Utf8,
+        },
+        NodeType::Array(array) => {
+            let item = match &array.items {
+                None => Field::new_list_field(DataType::Null, true),
+                Some(items) => field(Field::LIST_FIELD_DEFAULT_NAME, items, true, options),
+            };
+            DataType::List(Arc::new(item))
+        }
+        NodeType::Object(object) => DataType::Struct(fields(&object.properties, options)),
+        NodeType::Any(any) => any_field("", any, true, options).data_type().clone(),
+        NodeType::Recursive(_) => json_field("", true, options).data_type().clone(),
+    }
+}
+
+/// a field with JSON-encoded values
+fn json_field(name: &str, nullable: bool, options: &ArrowOptions) -> Field {
+    let data_type = match options.any_fallback {
+        AnyFallback::LargeJson => DataType::LargeUtf8,
+        AnyFallback::Json | AnyFallback::Union => DataType::Utf8,
+    };
+    let metadata = HashMap::from([("ARROW:extension:name".to_string(), "arrow.json".to_string())]);
+
+    Field::new(name, data_type, nullable).with_metadata(metadata)
+}
+
+fn any_field(name: &str, any: &AnyNode, nullable: bool, options: &ArrowOptions) -> Field {
+    match options.any_fallback {
+        AnyFallback::Json | AnyFallback::LargeJson => json_field(name, nullable, options),
+        AnyFallback::Union => {
+            let members: Vec<Field> = any
+                .nodes
+                .iter()
+                .map(|node_type| {
+                    Field::new(member_name(node_type), data_type(node_type, options), true)
+                })
+                .collect();
+            // members are distinct types, so there are never more than a handful of them
+            #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+            let type_ids = (0..members.len()).map(|i| i as i8);
+            let union_fields =
+                UnionFields::try_new(type_ids, members).expect("type ids are unique");
+
+            Field::new(
+                name,
+                DataType::Union(union_fields, UnionMode::Dense),
+                nullable,
+            )
+        }
+    }
+}
+
+/// name of a union-member, derived from its type
+fn member_name(node_type: &NodeType) -> String {
+    match node_type {
+        NodeType::Any(_) => "any".to_string(),
+        NodeType::Array(_) => "array".to_string(),
+        NodeType::Boolean => "boolean".to_string(),
+        NodeType::Integer(_) => "integer".to_string(),
+        NodeType::Null => "null".to_string(),
+        NodeType::Number(_) => "number".to_string(),
+        NodeType::Object(_) => "object".to_string(),
+        NodeType::Recursive(_) => "recursive".to_string(),
+        NodeType::String(s) => match &s.format {
+            None => "string".to_string(),
+            Some(format) => format!("string_{}", <&str>::from(format)),
+        },
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{AnyFallback, ArrowOptions, arrow_schema, render_arrow_text};
+    use crate::SchemaHypothesis;
+    use crate::error::RenderError;
+    use crate::model::NodeType;
+    use arrow_schema::{DataType, Field, TimeUnit, UnionMode};
+    use serde_json::{Value, json};
+    use std::sync::Arc;
+
+    fn hypothesis(documents: &[Value]) -> SchemaHypothesis {
+        documents.iter().fold(
+            SchemaHypothesis::new("id".to_string(), "events".to_string(), String::new()),
+            |hypothesis, document| hypothesis.merge(NodeType::from(document)),
+        )
+    }
+
+    #[test]
+    fn test_text() {
+        let hypothesis = hypothesis(&[
+            json!({"id": 1, "at": "2000-01-01T00:00:00Z", "tags": ["a"], "address": {"zip": 1}}),
+            json!({"id": 2, "at": null, "tags": [null], "address": {"zip": 2, "day": "2000-01-01"}}),
+        ]);
+
+        let actual = render_arrow_text(&hypothesis, &ArrowOptions::default()).unwrap();
+
+        assert_eq!(
+            actual,
+            r#"address: non-null Struct("day": Date32, "zip": non-null Int64)
+at: Timestamp(µs, "UTC")
+id: non-null Int64
+tags: non-null List(Utf8)"#
+        );
+    }
+
+    #[test]
+    fn test_any_json() {
+        let hypothesis = hypothesis(&[json!({"value": 1}), json!({"value": "one"})]);
+
+        let schema = arrow_schema(&hypothesis, &ArrowOptions::default()).unwrap();
+        let field = schema.field_with_name("value").unwrap();
+
+        assert_eq!(field.data_type(), &DataType::Utf8);
+        assert_eq!(
+            field.metadata().get("ARROW:extension:name"),
+            Some(&"arrow.json".to_string())
+        );
+    }
+
+    #[test]
+    fn test_any_union() {
+        let hypothesis = hypothesis(&[json!({"value": 1}), json!({"value": "12:00:00Z"})]);
+        let options = ArrowOptions {
+            any_fallback: AnyFallback::Union,
+        };
+
+        let schema = arrow_schema(&hypothesis, &options).unwrap();
+        let field = schema.field_with_name("value").unwrap();
+
+        let DataType::Union(members, UnionMode::Dense) = field.data_type() else {
+            panic!("expected a dense union, got {:?}", field.data_type());
+        };
+        let members: Vec<_> = members.iter().map(|(_, f)| f.clone()).collect();
+        assert_eq!(
+            members,
+            vec![
+                Arc::new(Field::new("integer", DataType::Int64, true)),
+                Arc::new(Field::new(
+                    "string_time",
+                    DataType::Time64(TimeUnit::Microsecond),
+                    true
+                )),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_root_not_an_object() {
+        let hypothesis = hypothesis(&[json!("text")]);
+
+        let actual = arrow_schema(&hypothesis, &ArrowOptions::default());
+
+        assert_eq!(actual, Err(RenderError::RootNotAnObject));
+    }
+}
@@ -0,0 +1,349 @@
+//! decode binary document formats (MessagePack, CBOR and BSON) into JSON
+//!
+//! Types JSON doesn't have are mapped to strings whose format is detected as usual, i.e. timestamps to
+//! RFC 3339 date-times and UUIDs to hyphenated UUIDs. Binary data becomes a base64-encoded string and
+//! non-string keys are converted to strings.
+
+use crate::error::DecodeError;
+use base64::Engine;
+use base64::prelude::BASE64_STANDARD;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::io::BufRead;
+use strum_macros::{Display, EnumString, VariantNames};
+
+/// msgpack's extension type for timestamps
+const MSGPACK_TIMESTAMP: i8 = -1;
+/// CBOR tags, see <https://www.iana.org/assignments/cbor-tags/cbor-tags.xhtml>
+const CBOR_EPOCH: u64 = 1;
+const CBOR_UUID: u64 = 37;
+
+/// encoding of documents, e.g. of kafka payloads
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    PartialEq,
+    Eq,
+    Default,
+    Display,
+    EnumString,
+    VariantNames,
+    Serialize,
+    Deserialize,
+)]
+#[strum(serialize_all = "kebab-case")]
+#[serde(rename_all = "kebab-case")]
+pub enum PayloadFormat {
+    #[default]
+    Json,
+    MessagePack,
+    Cbor,
+    Bson,
+}
+
+/// decode a single document
+pub fn decode_payload(payload: &[u8], format: PayloadFormat) -> Result<Value, DecodeError> {
+    if format == PayloadFormat::Json {
+        return Ok(serde_json::from_slice(payload)?);
+    }
+
+    let mut reader = payload;
+    let document = read_document(&mut reader, format)?.ok_or(DecodeError::Empty)?;
+    if !reader.is_empty() {
+        return Err(DecodeError::TrailingData);
+    }
+    Ok(document)
+}
+
+/// read the next document of a stream of concatenated documents, `None` at the end of the stream
+pub fn read_document(
+    reader: &mut impl BufRead,
+    format: PayloadFormat,
+) -> Result<Option<Value>, DecodeError> {
+    if format != PayloadFormat::Json && reader.fill_buf()?.is_empty() {
+        return Ok(None);
+    }
+
+    let document = match format {
+        PayloadFormat::Json => {
+            let mut documents = serde_json::Deserializer::from_reader(reader).into_iter();
+            return documents.next().transpose().map_err(DecodeError::from);
+        }
+        PayloadFormat::MessagePack => msgpack_to_json(rmpv::decode::read_value(reader)?),
+        PayloadFormat::Cbor => cbor_to_json(ciborium::de::from_reader(reader)?),
+        PayloadFormat::Bson => {
+            bson_to_json(bson::Bson::Document(bson::Document::from_reader(reader)?))
+        }
+    };
+    Ok(Some(document))
+}
+
+fn msgpack_to_json(value: rmpv::Value) -> Value {
+    match value {
+        rmpv::Value::Nil => Value::Null,
+        rmpv::Value::Boolean(b) => Value::Bool(b),
+        rmpv::Value::Integer(i) => match (i.as_i64(), i.as_u64()) {
+            (Some(i), _) => Value::from(i),
+            (_, Some(u)) => Value::from(u),
+            _ => Value::Null,
+        },
+        rmpv::Value::F32(f) => Value::from(f64::from(f)),
+        rmpv::Value::F64(f) => Value::from(f),
+        rmpv::Value::String(s) => Value::String(String::from_utf8_lossy(s.as_bytes()).into_owned()),
+        rmpv::Value::Binary(bytes) => binary(&bytes),
+        rmpv::Value::Array(values) => {
+            Value::Array(values.into_iter().map(msgpack_to_json).collect())
+        }
+        rmpv::Value::Map(entries) => Value::Object(
+            entries
+                .into_iter()
+                .map(|(key, value)| (key_string(msgpack_to_json(key)), msgpack_to_json(value)))
+                .collect(),
+        ),
+        rmpv::Value::Ext(MSGPACK_TIMESTAMP, data) => match msgpack_timestamp(&data) {
+            Some(millis) => datetime(millis),
+            None => binary(&data),
+        },
+        rmpv::Value::Ext(_, data) => binary(&data),
+    }
+}
+
+/// milliseconds since the epoch, see <https://github.com/msgpack/msgpack/blob/master/spec.md#timestamp-extension-type>
+fn msgpack_timestamp(data: &[u8]) -> Option<i64> {
+    let (seconds, nanos) = match data.len() {
+        4 => (i64::from(u32::from_be_bytes(data.try_into().ok()?)), 0),
+        8 => {
+            let value = u64::from_be_bytes(data.try_into().ok()?);
+            ((value & 0x3_ffff_ffff) as i64, (value >> 34) as i64)
+        }
+        12 => (
+            i64::from_be_bytes(data[4..].try_into().ok()?),
+            i64::from(u32::from_be_bytes(data[..4].try_into().ok()?)),
+        ),
+        _ => return None,
+    };
+    seconds.checked_mul(1000)?.checked_add(nanos / 1_000_000)
+}
+
+fn cbor_to_json(value: ciborium::Value) -> Value {
+    match value {
+        ciborium::Value::Null => Value::Null,
+        ciborium::Value::Bool(b) => Value::Bool(b),
+        ciborium::Value::Integer(i) => {
+            let i = i128::from(i);
+            i64::try_from(i)
+                .map(Value::from)
+                .or_else(|_| u64::try_from(i).map(Value::from))
+                // beyond JSON's integers
+                .unwrap_or_else(|_| Value::from(i as f64))
+        }
+        ciborium::Value::Float(f) => Value::from(f),
+        ciborium::Value::Text(s) => Value::String(s),
+        ciborium::Value::Bytes(bytes) => binary(&bytes),
+        ciborium::Value::Array(values) => {
+            Value::Array(values.into_iter().map(cbor_to_json).collect())
+        }
+        ciborium::Value::Map(entries) => Value::Object(
+            entries
+                .into_iter()
+                .map(|(key, value)| (key_string(cbor_to_json(key)), cbor_to_json(value)))
+                .collect(),
+        ),
+        ciborium::Value::Tag(CBOR_EPOCH, value) => {
+            let seconds = match *value {
+                ciborium::Value::Integer(i) => i64::try_from(i).ok().map(|i| i as f64),
+                ciborium::Value::Float(f) => Some(f),
+                _ => None,
+            };
+            match seconds {
+                Some(seconds) => datetime((seconds * 1000.0) as i64),
+                None => Value::Null,
+            }
+        }
+        ciborium::Value::Tag(CBOR_UUID, value) => match *value {
+            ciborium::Value::Bytes(bytes) => uuid(&bytes).unwrap_or_else(|| binary(&bytes)),
+            value => cbor_to_json(value),
+        },
+        // incl. date-time strings (tag 0), they are detected as such anyway
+        ciborium::Value::Tag(_, value) => cbor_to_json(*value),
+        _ => Value::Null,
+    }
+}
+
+fn bson_to_json(value: bson::Bson) -> Value {
+    match value {
+        bson::Bson::Null | bson::Bson::Undefined | bson::Bson::MinKey | bson::Bson::MaxKey => {
+            Value::Null
+        }
+        bson::Bson::Boolean(b) => Value::Bool(b),
+        bson::Bson::Int32(i) => Value::from(i),
+        bson::Bson::Int64(i) => Value::from(i),
+        bson::Bson::Double(f) => Value::from(f),
+        bson::Bson::Decimal128(d) => d
+            .to_string()
+            .parse::<f64>()
+            .map_or_else(|_| Value::String(d.to_string()), Value::from),
+        bson::Bson::String(s) | bson::Bson::Symbol(s) | bson::Bson::JavaScriptCode(s) => {
+            Value::String(s)
+        }
+        bson::Bson::JavaScriptCodeWithScope(code) => Value::String(code.code),
+        bson::Bson::RegularExpression(regex) => Value::String(regex.pattern),
+        // a 24 characters hex string, there's no (standard) format for it
+        bson::Bson::ObjectId(id) => Value::String(id.to_hex()),
+        bson::Bson::DateTime(datetime) => self::datetime(datetime.timestamp_millis()),
+        bson::Bson::Timestamp(timestamp) => datetime(i64::from(timestamp.time) * 1000),
+        bson::Bson::Binary(binary) => match binary.subtype {
+            bson::spec::BinarySubtype::Uuid | bson::spec::BinarySubtype::UuidOld => {
+                uuid(&binary.bytes).unwrap_or_else(|| self::binary(&binary.bytes))
+            }
+            _ => self::binary(&binary.bytes),
+        },
+        bson::Bson::DbPointer(_) => Value::Null,
+        bson::Bson::Array(values) => Value::Array(values.into_iter().map(bson_to_json).collect()),
+        bson::Bson::Document(document) => Value::Object(
+            document
+                .into_iter()
+                .map(|(key, value)| (key, bson_to_json(value)))
+                .collect::<Map<_, _>>(),
+        ),
+    }
+}
+
+fn key_string(key: Value) -> String {
+    match key {
+        Value::String(s) => s,
+        key => key.to_string(),
+    }
+}
+
+fn binary(bytes: &[u8]) -> Value {
+    Value::String(BASE64_STANDARD.encode(bytes))
+}
+
+/// RFC 3339 date-time of milliseconds since the epoch
+fn datetime(millis: i64) -> Value {
+    let datetime = bson::DateTime::from_millis(millis);
+    Value::String(
+        datetime
+            .try_to_rfc3339_string()
+            .unwrap_or_else(|_| datetime.to_string()),
+    )
+}
+
+/// hyphenated UUID of 16 bytes
+fn uuid(bytes: &[u8]) -> Option<Value> {
+    if bytes.len() != 16 {
+        return None;
+    }
+    let hex: String = bytes.iter().map(|b| format!("{b:02x}")).collect();
+    Some(Value::String(format!(
+        "{}-{}-{}-{}-{}",
+        &hex[..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..]
+    )))
+}
+
+/// read all documents of a stream, for tests
+#[cfg(test)]
+fn read_all(mut reader: &[u8], format: PayloadFormat) -> Vec<Value> {
+    std::iter::from_fn(|| read_document(&mut reader, format).unwrap()).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::{PayloadFormat, decode_payload, read_all};
+    use crate::error::DecodeError;
+    use serde_json::json;
+
+    #[test]
+    fn test_message_pack() {
+        let mut payload = vec![];
+        let timestamp = rmpv::Value::Ext(-1, 946_684_800u32.to_be_bytes().to_vec());
+        let value = rmpv::Value::Map(vec![
+            ("id".into(), 1.into()),
+            ("price".into(), 1.5.into()),
+            ("data".into(), rmpv::Value::Binary(vec![1, 2])),
+            ("created".into(), timestamp),
+            (1.into(), rmpv::Value::Nil),
+        ]);
+        rmpv::encode::write_value(&mut payload, &value).unwrap();
+        rmpv::encode::write_value(&mut payload, &rmpv::Value::from("next")).unwrap();
+
+        assert_eq!(
+            read_all(&payload, PayloadFormat::MessagePack),
+            vec![
+                json!({"id": 1, "price": 1.5, "data": "AQI=", "created": "2000-01-01T00:00:00Z", "1": null}),
+                json!("next")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_cbor() {
+        let uuid = vec![0x12; 16];
+        let value = ciborium::Value::Map(vec![
+            ("id".into(), ciborium::Value::Integer(1.into())),
+            (
+                "created".into(),
+                ciborium::Value::Tag(1, Box::new(ciborium::Value::Integer(946_684_800.into()))),
+            ),
+            (
+                "uuid".into(),
+                ciborium::Value::Tag(37, Box::new(ciborium::Value::Bytes(uuid))),
+            ),
+        ]);
+        let mut payload = vec![];
+        ciborium::ser::into_writer(&value, &mut payload).unwrap();
+
+        assert_eq!(
+            decode_payload(&payload, PayloadFormat::Cbor).unwrap(),
+            json!({"id": 1, "created": "2000-01-01T00:00:00Z", "uuid": "12121212-1212-1212-1212-121212121212"})
+        );
+    }
+
+    #[test]
+    fn test_bson() {
+        let id = bson::oid::ObjectId::parse_str("507f1f77bcf86cd799439011").unwrap();
+        let document = bson::doc! {
+            "_id": id,
+            "count": 1_i32,
+            "total": 2_i64,
+            "created": bson::DateTime::from_millis(946_684_800_000),
+            "uuid": bson::Binary { subtype: bson::spec::BinarySubtype::Uuid, bytes: vec![0xab; 16] },
+            "tags": ["a"],
+        };
+        let mut payload = vec![];
+        document.to_writer(&mut payload).unwrap();
+        document.to_writer(&mut payload).unwrap();
+
+        let expected = json!({
+            "_id": "507f1f77bcf86cd799439011",
+            "count": 1,
+            "total": 2,
+            "created": "2000-01-01T00:00:00Z",
+            "uuid": "abababab-abab-abab-abab-abababababab",
+            "tags": ["a"]
+        });
+        assert_eq!(
+            read_all(&payload, PayloadFormat::Bson),
+            vec![expected.clone(), expected]
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        assert!(matches!(
+            decode_payload(&[], PayloadFormat::Cbor),
+            Err(DecodeError::Empty)
+        ));
+        assert!(matches!(
+            decode_payload(&[0xc0, 0xc0], PayloadFormat::MessagePack),
+            Err(DecodeError::TrailingData)
+        ));
+        assert!(decode_payload(&[0x01, 0x00], PayloadFormat::Bson).is_err());
+    }
+}
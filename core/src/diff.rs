@@ -0,0 +1,416 @@
+//! structural diff between two hypotheses, e.g. two versions of the schema of a topic
+//!
+//! Changes are listed with the JSON path of the values within documents, like the rows of the
+//! [docs](crate::render_markdown), e.g. `$.items[*].sku`. Documents embedded into strings are marked with
+//! `(json)`, e.g. `$.payload(json).id`.
+
+use crate::SchemaHypothesis;
+use crate::docs::{property_path, type_label};
+use crate::model::{NodeType, ObjectNode};
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::fmt::{Display, Formatter};
+
+/// a change of the values at `path`
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Change {
+    /// JSON path of the changed values, e.g. `$.items[*].sku`
+    pub path: String,
+    #[serde(flatten)]
+    pub kind: ChangeKind,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(tag = "change", rename_all = "kebab-case")]
+pub enum ChangeKind {
+    PropertyAdded {
+        #[serde(rename = "type")]
+        node_type: String,
+        required: bool,
+    },
+    PropertyRemoved {
+        #[serde(rename = "type")]
+        node_type: String,
+    },
+    BecameOptional,
+    BecameRequired,
+    /// the new types include the old ones, e.g. `integer` to `integer | string`
+    TypeWidened {
+        from: String,
+        to: String,
+    },
+    /// the old types include the new ones
+    TypeNarrowed {
+        from: String,
+        to: String,
+    },
+    TypeChanged {
+        from: String,
+        to: String,
+    },
+    /// `None` for strings of any format
+    FormatChanged {
+        from: Option<String>,
+        to: Option<String>,
+    },
+}
+
+impl Display for Change {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: ", self.path)?;
+        match &self.kind {
+            ChangeKind::PropertyAdded {
+                node_type,
+                required,
+            } => {
+                let required = if *required { "required" } else { "optional" };
+                write!(f, "property added ({node_type}, {required})")
+            }
+            ChangeKind::PropertyRemoved { node_type } => {
+                write!(f, "property removed (was {node_type})")
+            }
+            ChangeKind::BecameOptional => write!(f, "became optional"),
+            ChangeKind::BecameRequired => write!(f, "became required"),
+            ChangeKind::TypeWidened { from, to } => write!(f, "type widened from {from} to {to}"),
+            ChangeKind::TypeNarrowed { from, to } => {
+                write!(f, "type narrowed from {from} to {to}")
+            }
+            ChangeKind::TypeChanged { from, to } => write!(f, "type changed from {from} to {to}"),
+            ChangeKind::FormatChanged { from, to } => write!(
+                f,
+                "format changed from {} to {}",
+                from.as_deref().unwrap_or("any"),
+                to.as_deref().unwrap_or("any")
+            ),
+        }
+    }
+}
+
+/// the changes from the `old` to the `new` hypothesis, in the order of the paths
+#[must_use]
+pub fn diff_hypotheses(old: &SchemaHypothesis, new: &SchemaHypothesis) -> Vec<Change> {
+    let mut changes = vec![];
+    diff_nodes(old.root.as_ref(), new.root.as_ref(), "$", &mut changes);
+    changes
+}
+
+fn diff_nodes(
+    old: Option<&NodeType>,
+    new: Option<&NodeType>,
+    path: &str,
+    changes: &mut Vec<Change>,
+) {
+    let mut push = |kind| {
+        changes.push(Change {
+            path: path.to_string(),
+            kind,
+        });
+    };
+
+    let (old_kinds, new_kinds) = (kinds(old), kinds(new));
+    if old_kinds != new_kinds {
+        let (from, to) = (kinds_label(&old_kinds), kinds_label(&new_kinds));
+        push(if old_kinds.is_subset(&new_kinds) {
+            ChangeKind::TypeWidened { from, to }
+        } else if old_kinds.is_superset(&new_kinds) {
+            ChangeKind::TypeNarrowed { from, to }
+        } else {
+            ChangeKind::TypeChanged { from, to }
+        });
+    }
+
+    if let (Some(from), Some(to)) = (formats(old), formats(new)) {
+        if from != to {
+            push(ChangeKind::FormatChanged {
+                from: formats_label(&from),
+                to: formats_label(&to),
+            });
+        }
+    }
+
+    let find = |node, kind: fn(&NodeType) -> bool| members(node).into_iter().find(|n| kind(n));
+    if let (Some(NodeType::Object(old)), Some(NodeType::Object(new))) = (
+        find(old, NodeType::is_object),
+        find(new, NodeType::is_object),
+    ) {
+        diff_objects(old, new, path, changes);
+    }
+    if let (Some(NodeType::Array(old)), Some(NodeType::Array(new))) =
+        (find(old, NodeType::is_array), find(new, NodeType::is_array))
+    {
+        diff_nodes(
+            old.items.as_deref(),
+            new.items.as_deref(),
+            &format!("{path}[*]"),
+            changes,
+        );
+    }
+
+    // documents embedded with the same encoding, otherwise the kinds differ
+    let content = |node| {
+        members(node).into_iter().find_map(|n| match n {
+            NodeType::String(s) => s.content.as_ref(),
+            _ => None,
+        })
+    };
+    if let (Some(old), Some(new)) = (content(old), content(new)) {
+        if old.encoding == new.encoding {
+            diff_nodes(
+                Some(&old.schema),
+                Some(&new.schema),
+                &format!("{path}(json)"),
+                changes,
+            );
+        }
+    }
+}
+
+fn diff_objects(old: &ObjectNode, new: &ObjectNode, path: &str, changes: &mut Vec<Change>) {
+    let keys: BTreeSet<&String> = old.properties.keys().chain(new.properties.keys()).collect();
+
+    for key in keys {
+        let path = property_path(path, key);
+        let change = |kind| Change {
+            path: path.clone(),
+            kind,
+        };
+        match (old.properties.get(key), new.properties.get(key)) {
+            (Some(old), Some(new)) => {
+                match (old.required, new.required) {
+                    (true, false) => changes.push(change(ChangeKind::BecameOptional)),
+                    (false, true) => changes.push(change(ChangeKind::BecameRequired)),
+                    _ => {}
+                }
+                diff_nodes(Some(&old.node_type), Some(&new.node_type), &path, changes);
+            }
+            (Some(old), None) => changes.push(change(ChangeKind::PropertyRemoved {
+                node_type: type_label(&old.node_type),
+            })),
+            (None, Some(new)) => changes.push(change(ChangeKind::PropertyAdded {
+                node_type: type_label(&new.node_type),
+                required: new.required,
+            })),
+            (None, None) => {}
+        }
+    }
+}
+
+/// the types of a union, or the node itself
+fn members(node: Option<&NodeType>) -> Vec<&NodeType> {
+    match node {
+        None => vec![],
+        Some(NodeType::Any(any)) => any.nodes.iter().collect(),
+        Some(node) => vec![node],
+    }
+}
+
+/// the kinds of types of a node, without looking into objects and arrays
+fn kinds(node: Option<&NodeType>) -> BTreeSet<String> {
+    members(node)
+        .into_iter()
+        .map(|node| match node {
+            NodeType::String(s) => match s.content.as_ref().map(|content| content.encoding) {
+                None => "string".to_string(),
+                Some(None) => "string (json)".to_string(),
+                Some(Some(encoding)) => format!("string ({} json)", <&str>::from(encoding)),
+            },
+            NodeType::Array(_) => "array".to_string(),
+            node => type_label(node),
+        })
+        .collect()
+}
+
+fn kinds_label(kinds: &BTreeSet<String>) -> String {
+    if kinds.is_empty() {
+        return "nothing".to_string();
+    }
+    kinds.iter().cloned().collect::<Vec<_>>().join(" | ")
+}
+
+/// the formats of the strings (not embedding documents) of a node, if there are any strings
+fn formats(node: Option<&NodeType>) -> Option<BTreeSet<Option<&'static str>>> {
+    let formats: BTreeSet<_> = members(node)
+        .into_iter()
+        .filter_map(|node| match node {
+            NodeType::String(s) if s.content.is_none() => Some(s.format.as_ref().map(<&str>::from)),
+            _ => None,
+        })
+        .collect();
+    (!formats.is_empty()).then_some(formats)
+}
+
+/// `None` for strings of any format
+fn formats_label(formats: &BTreeSet<Option<&'static str>>) -> Option<String> {
+    if formats.contains(&None) {
+        return None;
+    }
+    Some(
+        formats
+            .iter()
+            .flatten()
+            .copied()
+            .collect::<Vec<_>>()
+            .join(" | "),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Change, ChangeKind, diff_hypotheses};
+    use crate::model::{NodeType, StringNode};
+    use crate::{SchemaHypothesis, render_schema};
+    use serde_json::{Value, json};
+
+    fn hypothesis(documents: &[Value]) -> SchemaHypothesis {
+        documents.iter().fold(
+            SchemaHypothesis::new("id".to_string(), "order".to_string(), String::new()),
+            |hypothesis, document| hypothesis.merge(NodeType::from(document)),
+        )
+    }
+
+    fn change(path: &str, kind: ChangeKind) -> Change {
+        Change {
+            path: path.to_string(),
+            kind,
+        }
+    }
+
+    #[test]
+    fn test_diff() {
+        let old = hypothesis(&[
+            json!({"id": 1, "at": "2000-01-01", "customer": {"name": "a"}, "items": [{"sku": "a"}], "legacy": true}),
+        ]);
+        let new = hypothesis(&[
+            json!({"id": 1, "at": "2000-01-01T00:00:00Z", "items": [{"sku": 1, "count": 2}], "note": null}),
+            json!({"id": "2", "at": "2000-01-01T00:00:00Z", "customer": {"name": "b"}, "items": [], "note": "a"}),
+        ]);
+
+        let actual = diff_hypotheses(&old, &new);
+
+        assert_eq!(
+            actual,
+            vec![
+                change(
+                    "$.at",
+                    ChangeKind::FormatChanged {
+                        from: Some("date".to_string()),
+                        to: Some("date-time".to_string())
+                    }
+                ),
+                change("$.customer", ChangeKind::BecameOptional),
+                change(
+                    "$.id",
+                    ChangeKind::TypeWidened {
+                        from: "integer".to_string(),
+                        to: "integer | string".to_string()
+                    }
+                ),
+                change(
+                    "$.items[*].count",
+                    ChangeKind::PropertyAdded {
+                        node_type: "integer".to_string(),
+                        required: true
+                    }
+                ),
+                change(
+                    "$.items[*].sku",
+                    ChangeKind::TypeChanged {
+                        from: "string".to_string(),
+                        to: "integer".to_string()
+                    }
+                ),
+                change(
+                    "$.legacy",
+                    ChangeKind::PropertyRemoved {
+                        node_type: "boolean".to_string()
+                    }
+                ),
+                change(
+                    "$.note",
+                    ChangeKind::PropertyAdded {
+                        node_type: "null | string".to_string(),
+                        required: true
+                    }
+                ),
+            ]
+        );
+        assert_eq!(
+            actual.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            vec![
+                "$.at: format changed from date to date-time",
+                "$.customer: became optional",
+                "$.id: type widened from integer to integer | string",
+                "$.items[*].count: property added (integer, required)",
+                "$.items[*].sku: type changed from string to integer",
+                "$.legacy: property removed (was boolean)",
+                "$.note: property added (null | string, required)",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_no_changes() {
+        let old = hypothesis(&[json!({"id": 1, "tags": ["a"]})]);
+        let schema: Value = serde_json::from_str(&render_schema(&old)).unwrap();
+        let parsed = crate::parse_schema(&schema).unwrap();
+
+        assert_eq!(diff_hypotheses(&old, &old), vec![]);
+        assert_eq!(diff_hypotheses(&old, &parsed), vec![]);
+    }
+
+    #[test]
+    fn test_embedded_and_empty() {
+        let embedded = |document: Value| {
+            let mut hypothesis = hypothesis(&[]);
+            hypothesis.root = Some(
+                StringNode::embedded(None, NodeType::from(&json!({"payload": document}))).into(),
+            );
+            hypothesis
+        };
+        let old = embedded(json!({"id": 1}));
+        let new = embedded(json!({"id": 1.5}));
+
+        assert_eq!(
+            diff_hypotheses(&old, &new),
+            vec![change(
+                "$(json).payload.id",
+                ChangeKind::TypeChanged {
+                    from: "integer".to_string(),
+                    to: "number".to_string()
+                }
+            )]
+        );
+        assert_eq!(
+            diff_hypotheses(&hypothesis(&[]), &old),
+            vec![change(
+                "$",
+                ChangeKind::TypeWidened {
+                    from: "nothing".to_string(),
+                    to: "string (json)".to_string()
+                }
+            )]
+        );
+    }
+
+    #[test]
+    fn test_serialize() {
+        let changes = vec![
+            change("$.a", ChangeKind::BecameRequired),
+            change(
+                "$.b",
+                ChangeKind::FormatChanged {
+                    from: None,
+                    to: Some("uuid".to_string()),
+                },
+            ),
+        ];
+
+        assert_eq!(
+            serde_json::to_value(&changes).unwrap(),
+            json!([
+                {"path": "$.a", "change": "became-required"},
+                {"path": "$.b", "change": "format-changed", "from": null, "to": "uuid"}
+            ])
+        );
+    }
+}
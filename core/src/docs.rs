@@ -0,0 +1,254 @@
+//! human-readable documentation of a [`SchemaHypothesis`]: a table with one row per property
+
+use crate::SchemaHypothesis;
+use crate::model::{NodeType, ObjectNode};
+
+struct Row {
+    /// JSON path of the property, e.g. `$.items[*].sku`
+    path: String,
+    node_type: String,
+    format: String,
+    required: bool,
+}
+
+/// render the hypothesis as Markdown document
+#[must_use]
+pub fn render_markdown(schema: &SchemaHypothesis) -> String {
+    let mut lines = vec![format!("# {}", schema.title)];
+    if !schema.description.is_empty() {
+        lines.push(String::new());
+        lines.push(schema.description.clone());
+    }
+    lines.push(String::new());
+    lines.push(format!("Schema-ID: `{}`", schema.id));
+    lines.push(String::new());
+
+    match &schema.root {
+        None => lines.push("No documents have been harvested yet.".to_string()),
+        Some(root) => {
+            lines.push(format!("Type: {}", type_label(root)));
+            lines.push(String::new());
+            lines.push("| Path | Type | Format | Required |".to_string());
+            lines.push("|------|------|--------|----------|".to_string());
+            lines.extend(rows(root).into_iter().map(|row| {
+                format!(
+                    "| `{}` | {} | {} | {} |",
+                    row.path.replace('|', "\\|"),
+                    escape_markdown(&row.node_type),
+                    escape_markdown(&row.format),
+                    if row.required { "yes" } else { "no" }
+                )
+            }));
+        }
+    }
+
+    lines.join("\n")
+}
+
+/// render the hypothesis as self-contained HTML document
+#[must_use]
+pub fn render_html(schema: &SchemaHypothesis) -> String {
+    let title = escape_html(&schema.title);
+
+    let body = match &schema.root {
+        None => "<p>No documents have been harvested yet.</p>".to_string(),
+        Some(root) => {
+            let rows: String = rows(root)
+                .into_iter()
+                .map(|row| {
+                    format!(
+                        "      <tr><td><code>{}</code></td><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+                        escape_html(&row.path),
+                        escape_html(&row.node_type),
+                        escape_html(&row.format),
+                        if row.required { "yes" } else { "no" }
+                    )
+                })
+                .collect();
+            format!(
+                "<p>Type: {}</p>\n  <table>\n    <thead>\n      <tr><th>Path</th><th>Type</th><th>Format</th><th>Required</th></tr>\n    </thead>\n    <tbody>\n{rows}    </tbody>\n  </table>",
+                escape_html(&type_label(root))
+            )
+        }
+    };
+
+    format!(
+        r#"<!DOCTYPE html>
+<html>
+<head>
+  <meta charset="utf-8">
+  <title>{title}</title>
+  <style>
+    body {{ font-family: sans-serif; margin: 2em; }}
+    table {{ border-collapse: collapse; }}
+    th, td {{ border: 1px solid #ccc; padding: 0.25em 0.5em; text-align: left; }}
+    th {{ background: #eee; }}
+  </style>
+</head>
+<body>
+  <h1>{title}</h1>
+  <p>{}</p>
+  <p>Schema-ID: <code>{}</code></p>
+  {body}
+</body>
+</html>"#,
+        escape_html(&schema.description),
+        escape_html(&schema.id),
+    )
+}
+
+fn rows(root: &NodeType) -> Vec<Row> {
+    let mut rows = vec![];
+    collect_nested(root, "$", &mut rows);
+    rows
+}
+
+/// collect rows for all properties of the objects within the given node
+fn collect_nested(node_type: &NodeType, path: &str, rows: &mut Vec<Row>) {
+    match node_type {
+        NodeType::Object(object) => collect_properties(object, path, rows),
+        NodeType::Array(array) => {
+            if let Some(items) = &array.items {
+                collect_nested(items, &format!("{path}[*]"), rows);
+            }
+        }
+        NodeType::Any(any) => {
+            for node_type in &any.nodes {
+                collect_nested(node_type, path, rows);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn collect_properties(object: &ObjectNode, path: &str, rows: &mut Vec<Row>) {
+    for (key, property) in &object.properties {
+        let path = property_path(path, key);
+
+        rows.push(Row {
+            path: path.clone(),
+            node_type: type_label(&property.node_type),
+            format: formats(&property.node_type).join(", "),
+            required: property.required,
+        });
+        collect_nested(&property.node_type, &path, rows);
+    }
+}
+
+/// JSON path of a property of the object at `path`
+pub(crate) fn property_path(path: &str, key: &str) -> String {
+    if key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        format!("{path}.{key}")
+    } else {
+        format!("{path}[{}]", serde_json::Value::String(key.to_string()))
+    }
+}
+
+pub(crate) fn type_label(node_type: &NodeType) -> String {
+    match node_type {
+        NodeType::Any(any) => any
+            .nodes
+            .iter()
+            .map(type_label)
+            .collect::<Vec<_>>()
+            .join(" | "),
+        NodeType::Array(array) => match &array.items {
+            None => "array".to_string(),
+            Some(items) => format!("array<{}>", type_label(items)),
+        },
+        NodeType::Boolean => "boolean".to_string(),
+        NodeType::Integer(_) => "integer".to_string(),
+        NodeType::Null => "null".to_string(),
+        NodeType::Number(_) => "number".to_string(),
+        NodeType::Object(_) => "object".to_string(),
+        NodeType::Recursive(_) => "object (recursive)".to_string(),
+        NodeType::String(_) => "string".to_string(),
+    }
+}
+
+fn formats(node_type: &NodeType) -> Vec<&'static str> {
+    match node_type {
+        NodeType::String(s) => s.format.iter().map(<&str>::from).collect(),
+        NodeType::Any(any) => any.nodes.iter().flat_map(formats).collect(),
+        NodeType::Array(array) => array
+            .items
+            .iter()
+            .flat_map(|items| formats(items))
+            .collect(),
+        _ => vec![],
+    }
+}
+
+fn escape_markdown(s: &str) -> String {
+    s.replace('|', "\\|")
+        .replace('<', "\\<")
+        .replace('>', "\\>")
+}
+
+fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod test {
+    use super::{render_html, render_markdown};
+    use crate::SchemaHypothesis;
+    use crate::model::NodeType;
+    use serde_json::json;
+
+    fn hypothesis() -> SchemaHypothesis {
+        SchemaHypothesis::new(
+            "https://example.com/orders".to_string(),
+            "orders".to_string(),
+            "Orders placed in the shop".to_string(),
+        )
+        .merge(NodeType::from(&json!({
+            "id": 1,
+            "placed-at": "2000-01-01T00:00:00Z",
+            "items": [{"sku": "a", "count": 1}]
+        })))
+        .merge(NodeType::from(&json!({
+            "id": "f3fa7e18-549f-4ee1-8aeb-1bb8cbf7e956",
+            "placed-at": "2000-01-01T00:00:00Z",
+            "items": [{"sku": "a"}]
+        })))
+    }
+
+    #[test]
+    fn test_markdown() {
+        let actual = render_markdown(&hypothesis());
+
+        assert_eq!(
+            actual,
+            r#"# orders
+
+Orders placed in the shop
+
+Schema-ID: `https://example.com/orders`
+
+Type: object
+
+| Path | Type | Format | Required |
+|------|------|--------|----------|
+| `$.id` | integer \| string | uuid | yes |
+| `$.items` | array\<object\> |  | yes |
+| `$.items[*].count` | integer |  | no |
+| `$.items[*].sku` | string |  | yes |
+| `$["placed-at"]` | string | date-time | yes |"#
+        );
+    }
+
+    #[test]
+    fn test_html_is_escaped() {
+        let hypothesis =
+            SchemaHypothesis::new("id".to_string(), "<script>".to_string(), String::new());
+
+        let actual = render_html(&hypothesis);
+
+        assert!(actual.contains("<h1>&lt;script&gt;</h1>"));
+        assert!(actual.contains("No documents have been harvested yet."));
+    }
+}
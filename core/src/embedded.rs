@@ -0,0 +1,112 @@
+//! detect JSON documents embedded into strings, e.g. `{"payload": "{\"id\": 1}"}`
+//!
+//! Only objects and arrays count as embedded documents, strings like `"1"` or `"true"` are most likely meant
+//! as strings.
+
+use crate::model::ContentEncoding;
+use base64::Engine;
+use base64::prelude::BASE64_STANDARD;
+use serde::Deserialize;
+use serde_json::Value;
+use strum_macros::{Display, EnumString, VariantNames};
+
+/// which strings to harvest as embedded JSON documents
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, Display, EnumString, VariantNames, Deserialize,
+)]
+#[strum(serialize_all = "kebab-case")]
+#[serde(rename_all = "kebab-case")]
+pub enum EmbeddedJson {
+    /// strings are strings
+    #[default]
+    Ignore,
+    /// strings containing JSON
+    Json,
+    /// strings containing JSON, also base64-encoded
+    Base64,
+}
+
+/// the embedded document of a string, if any
+pub(crate) fn detect(
+    value: &str,
+    embedded_json: EmbeddedJson,
+) -> Option<(Option<ContentEncoding>, Value)> {
+    match embedded_json {
+        EmbeddedJson::Ignore => None,
+        EmbeddedJson::Json => decode(value, None).map(|document| (None, document)),
+        EmbeddedJson::Base64 => decode(value, None)
+            .map(|document| (None, document))
+            .or_else(|| {
+                let document = decode(value, Some(ContentEncoding::Base64))?;
+                Some((Some(ContentEncoding::Base64), document))
+            }),
+    }
+}
+
+/// the embedded document of a string, if detected with the given encoding
+pub(crate) fn detect_encoded(
+    value: &str,
+    embedded_json: EmbeddedJson,
+    encoding: Option<ContentEncoding>,
+) -> Option<Value> {
+    let (detected, document) = detect(value, embedded_json)?;
+    (detected == encoding).then_some(document)
+}
+
+/// the document embedded into a string with the given encoding
+fn decode(value: &str, encoding: Option<ContentEncoding>) -> Option<Value> {
+    match encoding {
+        None => parse_document(value.as_bytes()),
+        Some(ContentEncoding::Base64) => {
+            // JSON documents encode to base64 starting with `ey` (`{"`) or `W` (`[`)
+            if !value.starts_with("ey") && !value.starts_with('W') {
+                return None;
+            }
+            parse_document(&BASE64_STANDARD.decode(value).ok()?)
+        }
+    }
+}
+
+fn parse_document(bytes: &[u8]) -> Option<Value> {
+    let bytes = bytes.trim_ascii();
+    match (bytes.first(), bytes.last()) {
+        (Some(b'{'), Some(b'}')) | (Some(b'['), Some(b']')) => serde_json::from_slice(bytes).ok(),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{EmbeddedJson, detect};
+    use crate::model::ContentEncoding;
+    use serde_json::json;
+
+    #[test]
+    fn test_detect() {
+        assert_eq!(
+            detect("{\"id\": 1}", EmbeddedJson::Json),
+            Some((None, json!({"id": 1})))
+        );
+        assert_eq!(
+            detect(" [1, 2] ", EmbeddedJson::Json),
+            Some((None, json!([1, 2])))
+        );
+        assert_eq!(detect("{\"id\": 1}", EmbeddedJson::Ignore), None);
+        assert_eq!(detect("42", EmbeddedJson::Json), None);
+        assert_eq!(detect("[WARN] {disk} full", EmbeddedJson::Json), None);
+    }
+
+    #[test]
+    fn test_detect_base64() {
+        // {"id": 1}
+        let encoded = "eyJpZCI6IDF9";
+
+        assert_eq!(
+            detect(encoded, EmbeddedJson::Base64),
+            Some((Some(ContentEncoding::Base64), json!({"id": 1})))
+        );
+        assert_eq!(detect(encoded, EmbeddedJson::Json), None);
+        // "hello", valid base64 but no JSON
+        assert_eq!(detect("aGVsbG8=", EmbeddedJson::Base64), None);
+    }
+}
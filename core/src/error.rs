@@ -0,0 +1,233 @@
+use std::fmt::{Display, Formatter};
+
+/// errors of renderers that need a certain shape of the hypothesis, e.g. an object at its root
+#[derive(Debug, PartialEq, Eq)]
+pub enum RenderError {
+    /// the hypothesis has not seen any document yet
+    EmptyHypothesis,
+    /// the output format can only represent objects at the root
+    RootNotAnObject,
+    /// the output format can't represent an object without properties at the root, e.g. a table without columns
+    NoProperties,
+}
+
+impl Display for RenderError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RenderError::EmptyHypothesis => write!(f, "hypothesis is empty, nothing to render"),
+            RenderError::RootNotAnObject => write!(f, "root of the hypothesis is not an object"),
+            RenderError::NoProperties => {
+                write!(f, "root of the hypothesis is an object without properties")
+            }
+        }
+    }
+}
+
+impl std::error::Error for RenderError {}
+
+/// errors loading a saved hypothesis
+#[derive(Debug)]
+pub enum LoadError {
+    /// not a (valid) saved hypothesis
+    Json(serde_json::Error),
+    /// saved by a different (newer) version of schema-harvester
+    UnsupportedVersion(u64),
+}
+
+impl Display for LoadError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LoadError::Json(e) => write!(f, "invalid hypothesis: {e}"),
+            LoadError::UnsupportedVersion(version) => {
+                write!(f, "unsupported version {version} of saved hypothesis")
+            }
+        }
+    }
+}
+
+impl std::error::Error for LoadError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            LoadError::Json(e) => Some(e),
+            LoadError::UnsupportedVersion(_) => None,
+        }
+    }
+}
+
+impl From<serde_json::Error> for LoadError {
+    fn from(e: serde_json::Error) -> Self {
+        LoadError::Json(e)
+    }
+}
+
+/// errors parsing a JSON schema, `path` is the JSON pointer of the subschema within the document
+#[derive(Debug, PartialEq, Eq)]
+pub enum ParseError {
+    /// the (sub)schema is not an object
+    InvalidSchema { path: String },
+    /// `type` is not one of JSON schema's types, `type_name` is JSON-encoded
+    UnknownType { path: String, type_name: String },
+    /// `$ref` to anything but a subschema of the same document, `reference` is JSON-encoded
+    UnresolvableRef { path: String, reference: String },
+    /// a keyword that can't be represented, e.g. `allOf`
+    Unsupported { path: String, keyword: String },
+    /// a root schema without any information, e.g. `{}`
+    Unconstrained { path: String },
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseError::InvalidSchema { path } => write!(f, "{path}: schema must be an object"),
+            ParseError::UnknownType { path, type_name } => {
+                write!(f, "{path}: unknown type {type_name}")
+            }
+            ParseError::UnresolvableRef { path, reference } => {
+                write!(f, "{path}: can't resolve $ref {reference}")
+            }
+            ParseError::Unsupported { path, keyword } => {
+                write!(f, "{path}: \"{keyword}\" is not supported")
+            }
+            ParseError::Unconstrained { path } => {
+                write!(f, "{path}: schema does not restrict the type")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// errors parsing a [`DocumentPath`](crate::DocumentPath)
+#[derive(Debug, PartialEq, Eq)]
+pub enum PathError {
+    /// neither a JSON Pointer (starting with `/`) nor a JSONPath (starting with `$`)
+    UnknownSyntax { path: String },
+    /// `~` in a JSON Pointer not followed by `0` or `1`
+    InvalidEscape { path: String },
+    /// invalid or unsupported JSONPath (e.g. filters), `position` is the byte offset of the problem
+    InvalidSyntax { path: String, position: usize },
+}
+
+impl Display for PathError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PathError::UnknownSyntax { path } => write!(
+                f,
+                "{path}: expected a JSON Pointer (e.g. /data) or JSONPath (e.g. $.data)"
+            ),
+            PathError::InvalidEscape { path } => {
+                write!(f, "{path}: `~` must be followed by 0 or 1")
+            }
+            PathError::InvalidSyntax { path, position } => {
+                write!(f, "{path}: unsupported JSONPath at position {position}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for PathError {}
+
+/// errors of invalid [`SamplingOptions`](crate::SamplingOptions)
+#[derive(Debug, PartialEq)]
+pub enum SamplingError {
+    /// sample rate not within `(0, 1]`
+    InvalidSampleRate(f64),
+    /// not a duration like `90s`, `15m` or `2h`
+    InvalidDuration(String),
+    /// a reservoir without room for a single document
+    EmptyReservoir,
+    /// the reservoir is harvested at the end, it can't tell when the hypothesis is stable
+    StableReservoir,
+}
+
+impl Display for SamplingError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SamplingError::InvalidSampleRate(rate) => {
+                write!(f, "sample rate {rate} must be greater than 0 and at most 1")
+            }
+            SamplingError::InvalidDuration(duration) => write!(
+                f,
+                "{duration}: expected a duration like 500ms, 90s, 15m, 2h or 1d"
+            ),
+            SamplingError::EmptyReservoir => write!(f, "reservoir must hold at least 1 document"),
+            SamplingError::StableReservoir => write!(
+                f,
+                "stopping when stable is not supported with reservoir sampling"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SamplingError {}
+
+/// errors decoding a document, see [`PayloadFormat`](crate::PayloadFormat)
+#[derive(Debug)]
+pub enum DecodeError {
+    /// no document at all
+    Empty,
+    /// more data after the document
+    TrailingData,
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    MessagePack(rmpv::decode::Error),
+    Cbor(ciborium::de::Error<std::io::Error>),
+    Bson(bson::de::Error),
+}
+
+impl Display for DecodeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DecodeError::Empty => write!(f, "no document"),
+            DecodeError::TrailingData => write!(f, "trailing data after the document"),
+            DecodeError::Io(e) => write!(f, "{e}"),
+            DecodeError::Json(e) => write!(f, "invalid JSON: {e}"),
+            DecodeError::MessagePack(e) => write!(f, "invalid MessagePack: {e}"),
+            DecodeError::Cbor(e) => write!(f, "invalid CBOR: {e}"),
+            DecodeError::Bson(e) => write!(f, "invalid BSON: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            DecodeError::Empty | DecodeError::TrailingData => None,
+            DecodeError::Io(e) => Some(e),
+            DecodeError::Json(e) => Some(e),
+            DecodeError::MessagePack(e) => Some(e),
+            DecodeError::Cbor(e) => Some(e),
+            DecodeError::Bson(e) => Some(e),
+        }
+    }
+}
+
+impl From<std::io::Error> for DecodeError {
+    fn from(e: std::io::Error) -> Self {
+        DecodeError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for DecodeError {
+    fn from(e: serde_json::Error) -> Self {
+        DecodeError::Json(e)
+    }
+}
+
+impl From<rmpv::decode::Error> for DecodeError {
+    fn from(e: rmpv::decode::Error) -> Self {
+        DecodeError::MessagePack(e)
+    }
+}
+
+impl From<ciborium::de::Error<std::io::Error>> for DecodeError {
+    fn from(e: ciborium::de::Error<std::io::Error>) -> Self {
+        DecodeError::Cbor(e)
+    }
+}
+
+impl From<bson::de::Error> for DecodeError {
+    fn from(e: bson::de::Error) -> Self {
+        DecodeError::Bson(e)
+    }
+}
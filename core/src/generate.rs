@@ -1,27 +1,55 @@
+use serde::Deserialize;
 use serde_json::{Map, Value};
 use std::collections::{BTreeMap, BTreeSet};
 
+use crate::embedded::{EmbeddedJson, detect};
+use crate::merge::merge_into;
 use crate::model::{
-    ArrayNode, IntegerNode, NodeType, NumberNode, ObjectNode, ObjectProperty, StringNode,
+    AnyNode, ArrayNode, IntegerNode, NodeType, NumberNode, ObjectNode, ObjectProperty, StringNode,
 };
 
+/// options for harvesting documents, see [`SchemaHypothesis::observe_with`](crate::SchemaHypothesis::observe_with)
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+pub struct HarvestOptions {
+    /// which strings to harvest as embedded JSON documents
+    #[serde(default)]
+    pub embedded_json: EmbeddedJson,
+    /// don't fold recursive structures (trees, linked lists, …) into references, harvest each level separately
+    #[serde(default)]
+    pub no_recursion: bool,
+}
+
 impl From<&Value> for NodeType {
     fn from(dom: &Value) -> Self {
-        match dom {
-            Value::Null => NodeType::Null,
-            Value::Bool(_) => NodeType::Boolean,
-            Value::Number(i) if i.is_f64() => NumberNode::new().into(),
-            Value::Number(_) => IntegerNode::new().into(),
-            Value::String(s) => StringNode::from(s.as_str()).into(),
-            Value::Array(array_values) => {
-                ArrayNode::from(collect_types_of_items(array_values)).into()
-            }
-            Value::Object(props) => ObjectNode::new(generate_properties(props)).into(),
-        }
+        generate(dom, &HarvestOptions::default())
+    }
+}
+
+/// the node of a document, like `NodeType::from`, with options
+pub fn generate(dom: &Value, options: &HarvestOptions) -> NodeType {
+    match dom {
+        Value::Null => NodeType::Null,
+        Value::Bool(_) => NodeType::Boolean,
+        Value::Number(i) if i.is_f64() => NumberNode::new().into(),
+        Value::Number(_) => IntegerNode::new().into(),
+        Value::String(s) => string_node(s, options).into(),
+        Value::Array(array_values) => collect_types_of_items(array_values, options).into(),
+        Value::Object(props) => ObjectNode::new(generate_properties(props, options)).into(),
     }
 }
 
-fn generate_properties(properties: &Map<String, Value>) -> BTreeMap<String, ObjectProperty> {
+/// the node of a string, the embedded document is harvested if enabled
+pub(crate) fn string_node(s: &str, options: &HarvestOptions) -> StringNode {
+    match detect(s, options.embedded_json) {
+        Some((encoding, document)) => StringNode::embedded(encoding, generate(&document, options)),
+        None => StringNode::from(s),
+    }
+}
+
+fn generate_properties(
+    properties: &Map<String, Value>,
+    options: &HarvestOptions,
+) -> BTreeMap<String, ObjectProperty> {
     properties
         .iter()
         .map(|(key, value)| {
@@ -29,56 +57,30 @@ fn generate_properties(properties: &Map<String, Value>) -> BTreeMap<String, Obje
                 key.clone(),
                 ObjectProperty {
                     required: true,
-                    node_type: NodeType::from(value),
+                    node_type: generate(value, options),
                 },
             )
         })
         .collect()
 }
 
-/// given an array of Values, gather the different NodeTypes
-fn collect_types_of_items(array_values: &[Value]) -> BTreeSet<NodeType> {
-    let mut merged_obj_type: Option<NodeType> = None;
-    let mut merged_array_type: Option<NodeType> = None;
-    let mut types = BTreeSet::new();
-
-    // first, iterate over all values and collect the different types
+/// given an array of Values, gather the different NodeTypes, objects and arrays are merged into one each
+fn collect_types_of_items(array_values: &[Value], options: &HarvestOptions) -> ArrayNode {
+    let mut types = NodeType::Any(AnyNode::new(BTreeSet::new()));
     for value in array_values {
-        let value_type = NodeType::from(value);
-        match value_type {
-            NodeType::Object(ObjectNode { properties: _ }) => {
-                merged_obj_type = match merged_obj_type {
-                    Some(acc) => Some(crate::merge::merge_node_type(acc, value_type)),
-                    None => Some(value_type),
-                };
-            }
-            NodeType::Array(_) => {
-                merged_array_type = match merged_array_type {
-                    Some(acc) => Some(crate::merge::merge_node_type(acc, value_type)),
-                    None => Some(value_type),
-                }
-            }
-            _ => {
-                types.insert(value_type);
-            }
-        };
-    }
-    if let Some(node_type) = merged_obj_type {
-        types.insert(node_type);
-    }
-
-    if let Some(node_type) = merged_array_type {
-        types.insert(node_type);
+        merge_into(&mut types, generate(value, options));
     }
-
-    types
+    let NodeType::Any(types) = types else {
+        unreachable!()
+    };
+    ArrayNode::from(types.nodes)
 }
 
 #[cfg(test)]
 mod test {
     use crate::model::{
         AnyNode, ArrayNode, IntegerNode, NodeType, NumberNode, ObjectNode, ObjectProperty,
-        StringNode,
+        StringFormat, StringNode,
     };
     use maplit::{btreemap, btreeset};
     use serde_json::json;
@@ -131,6 +133,22 @@ mod test {
         assert_eq!(actual, expected);
     }
 
+    #[test]
+    fn test_array_strings_with_and_without_format() {
+        let dom = json!(["2000-01-01", "one"]);
+        assert_eq!(
+            NodeType::from(&dom),
+            ArrayNode::new(
+                AnyNode::new(btreeset![
+                    StringNode::new(Some(StringFormat::Date)).into(),
+                    StringNode::default().into()
+                ])
+                .into()
+            )
+            .into()
+        );
+    }
+
     #[test]
     fn test_array_all_int() {
         let dom = json!([10, 15, 25]);
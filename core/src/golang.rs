@@ -0,0 +1,324 @@
+//! generate Go structs (with `encoding/json` tags) from a [`SchemaHypothesis`]
+
+use crate::SchemaHypothesis;
+use crate::model::{NodeType, ObjectNode, StringFormat};
+use crate::naming::{Named, Names, pascal_case};
+use std::collections::BTreeSet;
+use strum_macros::{Display, EnumString, VariantNames};
+
+/// initialisms that are written all-uppercase in Go names, see <https://go.dev/wiki/CodeReviewComments#initialisms>
+const INITIALISMS: &[&str] = &[
+    "ACL", "API", "ASCII", "CPU", "CSS", "DNS", "EOF", "GUID", "HTML", "HTTP", "HTTPS", "ID", "IP",
+    "JSON", "SQL", "SSH", "TCP", "TLS", "TTL", "UDP", "UI", "UID", "URI", "URL", "UUID", "XML",
+];
+
+/// Go type for values of mixed types
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default, Display, EnumString, VariantNames)]
+#[strum(serialize_all = "kebab-case")]
+pub enum GoAnyType {
+    /// `json.RawMessage`, decode later
+    #[default]
+    RawMessage,
+    /// `any`, decoded by `encoding/json` into maps, slices etc.
+    Any,
+}
+
+#[derive(Clone, Debug)]
+pub struct GoOptions {
+    pub package: String,
+    pub any_type: GoAnyType,
+}
+
+impl Default for GoOptions {
+    fn default() -> Self {
+        Self {
+            package: "schema".to_string(),
+            any_type: GoAnyType::default(),
+        }
+    }
+}
+
+/// generate a Go file with one struct per object of the hypothesis, the root is named after the title
+#[must_use]
+pub fn render_go(schema: &SchemaHypothesis, options: &GoOptions) -> String {
+    let mut imports = BTreeSet::new();
+
+    let declarations: Vec<String> = match &schema.root {
+        None => vec![],
+        Some(root) => {
+            let names = Names::collect(root, &schema.title);
+            let mut declarations = vec![];
+
+            if !root.is_object() {
+                let root_type = go_type(root, false, &names, options, &mut imports);
+                declarations.push(format!("type {} {root_type}", pascal_case(&schema.title)));
+            }
+
+            // the root first, nested types after the types using them
+            declarations.extend(names.ordered.iter().rev().filter_map(|(name, named)| {
+                match named {
+                    Named::Object(object) if !object.properties.is_empty() => {
+                        Some(render_struct(name, object, &names, options, &mut imports))
+                    }
+                    // unions are rendered as `json.RawMessage`/`any` and empty objects as map
+                    _ => None,
+                }
+            }));
+
+            declarations
+        }
+    };
+
+    let mut file = format!("package {}\n", options.package);
+    match imports.len() {
+        0 => {}
+        1 => file.push_str(&format!("\nimport \"{}\"\n", imports.first().unwrap())),
+        _ => {
+            file.push_str("\nimport (\n");
+            for import in imports {
+                file.push_str(&format!("\t\"{import}\"\n"));
+            }
+            file.push_str(")\n");
+        }
+    }
+    for declaration in declarations {
+        file.push('\n');
+        file.push_str(&declaration);
+        file.push('\n');
+    }
+
+    file
+}
+
+fn render_struct(
+    name: &str,
+    object: &ObjectNode,
+    names: &Names,
+    options: &GoOptions,
+    imports: &mut BTreeSet<&'static str>,
+) -> String {
+    let mut field_names = BTreeSet::new();
+
+    let fields: Vec<(String, String, String)> = object
+        .properties
+        .iter()
+        .map(|(key, property)| {
+            let mut field_name = field_name(key);
+            let mut suffix = 2;
+            while !field_names.insert(field_name.clone()) {
+                field_name = format!("{}{suffix}", self::field_name(key));
+                suffix += 1;
+            }
+
+            let optional = !property.required;
+            let field_type = go_type(&property.node_type, optional, names, options, imports);
+            let omitempty = if optional { ",omitempty" } else { "" };
+            let tag = format!("`json:{}`", go_string(&format!("{key}{omitempty}")));
+
+            (field_name, field_type, tag)
+        })
+        .collect();
+
+    // align like gofmt does
+    let name_width = fields.iter().map(|(n, _, _)| n.len()).max().unwrap_or(0);
+    let type_width = fields.iter().map(|(_, t, _)| t.len()).max().unwrap_or(0);
+    let fields: Vec<String> = fields
+        .into_iter()
+        .map(|(field_name, field_type, tag)| {
+            format!("\t{field_name:name_width$} {field_type:type_width$} {tag}")
+        })
+        .collect();
+
+    format!("type {name} struct {{\n{}\n}}", fields.join("\n"))
+}
+
+/// the go type of a value, `optional` values (i.e. not required or nullable) become pointers
+fn go_type(
+    node_type: &NodeType,
+    optional: bool,
+    names: &Names,
+    options: &GoOptions,
+    imports: &mut BTreeSet<&'static str>,
+) -> String {
+    let (node_type, nullable) = node_type.split_null();
+    let Some(node_type) = node_type else {
+        return any_type(options, imports);
+    };
+
+    let go_type = match node_type {
+        NodeType::Boolean => "bool".to_string(),
+        NodeType::Integer(_) => "int64".to_string(),
+        NodeType::Number(_) => "float64".to_string(),
+        NodeType::String(s) => match s.format {
+            Some(StringFormat::DateTime) => {
+                imports.insert("time");
+                "time.Time".to_string()
+            }
+            Some(StringFormat::Date | StringFormat::Time | StringFormat::Uuid) | None => {
+                "string".to_string()
+            }
+        },
+        NodeType::Array(array) => {
+            let items = match &array.items {
+                None => any_type(options, imports),
+                Some(items) => go_type(items, false, names, options, imports),
+            };
+            // slices are nil-able already
+            return format!("[]{items}");
+        }
+        NodeType::Object(object) if object.properties.is_empty() => {
+            return format!("map[string]{}", any_type(options, imports));
+        }
+        NodeType::Object(object) => names.object(object).to_string(),
+        // a struct can't contain itself, only a pointer to itself
+        NodeType::Recursive(recursive) => return format!("*{}", names.recursive(recursive)),
+        NodeType::Null | NodeType::Any(_) => return any_type(options, imports),
+    };
+
+    if optional || nullable {
+        format!("*{go_type}")
+    } else {
+        go_type
+    }
+}
+
+fn any_type(options: &GoOptions, imports: &mut BTreeSet<&'static str>) -> String {
+    match options.any_type {
+        GoAnyType::RawMessage => {
+            imports.insert("encoding/json");
+            "json.RawMessage".to_string()
+        }
+        GoAnyType::Any => "any".to_string(),
+    }
+}
+
+/// exported go name of a property, with go's initialisms, e.g. `user_id` becomes `UserID`
+fn field_name(key: &str) -> String {
+    let mut words: Vec<String> = vec![];
+    let mut current = String::new();
+    let mut previous_lower = false;
+    for c in key.chars() {
+        let boundary = !c.is_ascii_alphanumeric() || (c.is_ascii_uppercase() && previous_lower);
+        if boundary && !current.is_empty() {
+            words.push(std::mem::take(&mut current));
+        }
+        if c.is_ascii_alphanumeric() {
+            current.push(c);
+        }
+        previous_lower = c.is_ascii_lowercase() || c.is_ascii_digit();
+    }
+    if !current.is_empty() {
+        words.push(current);
+    }
+
+    let name: String = words
+        .iter()
+        .map(|word| {
+            let upper = word.to_ascii_uppercase();
+            if INITIALISMS.contains(&upper.as_str()) {
+                upper
+            } else {
+                word[..1].to_ascii_uppercase() + &word[1..]
+            }
+        })
+        .collect();
+
+    match name.chars().next() {
+        None => "Field".to_string(),
+        Some(c) if c.is_ascii_digit() => format!("F{name}"),
+        Some(_) => name,
+    }
+}
+
+fn go_string(s: &str) -> String {
+    // JSON string-escaping is a valid go string literal
+    serde_json::Value::String(s.to_string()).to_string()
+}
+
+#[cfg(test)]
+mod test {
+    use super::{GoAnyType, GoOptions, field_name, render_go};
+    use crate::SchemaHypothesis;
+    use crate::model::NodeType;
+    use serde_json::{Value, json};
+    use test_case::test_case;
+
+    fn hypothesis(documents: &[Value]) -> SchemaHypothesis {
+        documents.iter().fold(
+            SchemaHypothesis::new("id".to_string(), "order".to_string(), String::new()),
+            |hypothesis, document| hypothesis.merge(NodeType::from(document)),
+        )
+    }
+
+    #[test_case("id", "ID")]
+    #[test_case("user_id", "UserID")]
+    #[test_case("userId", "UserID")]
+    #[test_case("created-at", "CreatedAt")]
+    #[test_case("apiURL", "APIURL")]
+    #[test_case("1st", "F1st")]
+    fn test_field_name(key: &str, expected: &str) {
+        assert_eq!(field_name(key), expected);
+    }
+
+    #[test]
+    fn test_structs() {
+        let hypothesis = hypothesis(&[
+            json!({"id": 1, "created-at": "2000-01-01T00:00:00Z", "items": [{"sku": "a"}], "value": 1, "note": null}),
+            json!({"id": 2, "created-at": "2000-01-01T00:00:00Z", "items": [{"sku": "b"}], "value": "one", "note": "fragile", "customer": {"name": "a"}}),
+        ]);
+
+        let actual = render_go(&hypothesis, &GoOptions::default());
+
+        assert_eq!(
+            actual,
+            r#"package schema
+
+import (
+	"encoding/json"
+	"time"
+)
+
+type Order struct {
+	CreatedAt time.Time       `json:"created-at"`
+	Customer  *Customer       `json:"customer,omitempty"`
+	ID        int64           `json:"id"`
+	Items     []ItemsItem     `json:"items"`
+	Note      *string         `json:"note"`
+	Value     json.RawMessage `json:"value"`
+}
+
+type ItemsItem struct {
+	Sku string `json:"sku"`
+}
+
+type Customer struct {
+	Name string `json:"name"`
+}
+"#
+        );
+    }
+
+    #[test]
+    fn test_root_array() {
+        let hypothesis = hypothesis(&[json!([{"id": 1}, {"id": 2, "tags": {}}])]);
+        let options = GoOptions {
+            package: "orders".to_string(),
+            any_type: GoAnyType::Any,
+        };
+
+        let actual = render_go(&hypothesis, &options);
+
+        assert_eq!(
+            actual,
+            r#"package orders
+
+type Order []OrderItem
+
+type OrderItem struct {
+	ID   int64          `json:"id"`
+	Tags map[string]any `json:"tags,omitempty"`
+}
+"#
+        );
+    }
+}
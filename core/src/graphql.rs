@@ -0,0 +1,276 @@
+//! render a [`SchemaHypothesis`] as [GraphQL SDL](https://spec.graphql.org/October2021/#sec-Type-System)
+
+use crate::SchemaHypothesis;
+use crate::model::{NodeType, ObjectNode, StringFormat};
+use crate::naming::{Named, Names, object_union};
+use std::collections::BTreeSet;
+
+/// builtin types, the scalars used by the generated types and the root operation types
+const RESERVED: &[&str] = &[
+    "Boolean",
+    "Date",
+    "DateTime",
+    "Float",
+    "ID",
+    "Int",
+    "JSON",
+    "Mutation",
+    "Query",
+    "String",
+    "Subscription",
+    "Time",
+    "UUID",
+];
+
+/// render one `type` per object of the hypothesis, the root object is named after the title
+#[must_use]
+pub fn render_graphql(schema: &SchemaHypothesis) -> String {
+    let Some(root) = &schema.root else {
+        return String::new();
+    };
+
+    let names = Names::collect_reserving(root, &schema.title, RESERVED);
+    let mut scalars = BTreeSet::new();
+
+    // the root first, nested types after the types using them
+    let definitions: Vec<String> = names
+        .ordered
+        .iter()
+        .rev()
+        .filter_map(|(name, named)| match named {
+            Named::Object(object) if object.properties.is_empty() => None,
+            Named::Object(object) => Some(render_type(name, object, &names, &mut scalars)),
+            Named::Union(objects) if !has_members(objects) => None,
+            Named::Union(objects) => {
+                let members: Vec<_> = objects
+                    .iter()
+                    .filter(|object| !object.properties.is_empty())
+                    .map(|object| names.object(object))
+                    .collect();
+                Some(format!("union {name} = {}", members.join(" | ")))
+            }
+        })
+        .collect();
+
+    scalars
+        .into_iter()
+        .map(|scalar| format!("scalar {scalar}"))
+        .chain(definitions)
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// whether a union of objects has members to render, objects without properties are not rendered as types
+fn has_members(objects: &[&ObjectNode]) -> bool {
+    objects.iter().any(|object| !object.properties.is_empty())
+}
+
+fn render_type(
+    name: &str,
+    object: &ObjectNode,
+    names: &Names,
+    scalars: &mut BTreeSet<&'static str>,
+) -> String {
+    let mut field_names = BTreeSet::new();
+    let fields: Vec<String> = object
+        .properties
+        .iter()
+        .map(|(key, property)| {
+            // different keys may result in the same name, e.g. `a-b` and `a_b`
+            let mut name = field_name(key);
+            let mut suffix = 2;
+            while !field_names.insert(name.clone()) {
+                name = format!("{}{suffix}", field_name(key));
+                suffix += 1;
+            }
+            let field_type = field_type(&property.node_type, property.required, names, scalars);
+            format!("  {name}: {field_type}")
+        })
+        .collect();
+
+    format!("type {name} {{\n{}\n}}", fields.join("\n"))
+}
+
+/// the type of a field, with `!` if it's required and non-null
+fn field_type(
+    node_type: &NodeType,
+    required: bool,
+    names: &Names,
+    scalars: &mut BTreeSet<&'static str>,
+) -> String {
+    let (node_type, nullable) = node_type.split_null();
+
+    let field_type = match node_type {
+        None => json_scalar(scalars),
+        Some(node_type) => named_type(node_type, names, scalars),
+    };
+
+    if required && !nullable && node_type.is_some() {
+        format!("{field_type}!")
+    } else {
+        field_type
+    }
+}
+
+fn named_type(node_type: &NodeType, names: &Names, scalars: &mut BTreeSet<&'static str>) -> String {
+    match node_type {
+        NodeType::Boolean => "Boolean".to_string(),
+        NodeType::Integer(_) => "Int".to_string(),
+        NodeType::Number(_) => "Float".to_string(),
+        NodeType::String(s) => {
+            let scalar = match s.format {
+                None => return "String".to_string(),
+                Some(StringFormat::DateTime) => "DateTime",
+                Some(StringFormat::Date) => "Date",
+                Some(StringFormat::Time) => "Time",
+                Some(StringFormat::Uuid) => "UUID",
+            };
+            scalars.insert(scalar);
+            scalar.to_string()
+        }
+        NodeType::Array(array) => match &array.items {
+            None => format!("[{}]", json_scalar(scalars)),
+            Some(items) => format!("[{}]", field_type(items, true, names, scalars)),
+        },
+        NodeType::Object(object) if object.properties.is_empty() => json_scalar(scalars),
+        NodeType::Object(object) => names.object(object).to_string(),
+        NodeType::Recursive(recursive) => names.recursive(recursive).to_string(),
+        NodeType::Any(any) if object_union(any).is_some_and(|objects| has_members(&objects)) => {
+            names.union(any).to_string()
+        }
+        NodeType::Null | NodeType::Any(_) => json_scalar(scalars),
+    }
+}
+
+/// fallback for everything that has no GraphQL representation, e.g. mixed scalar unions
+fn json_scalar(scalars: &mut BTreeSet<&'static str>) -> String {
+    scalars.insert("JSON");
+    "JSON".to_string()
+}
+
+/// GraphQL names are restricted to `[_A-Za-z][_0-9A-Za-z]*`
+fn field_name(key: &str) -> String {
+    let name: String = key
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+
+    match name.chars().next() {
+        None => "_".to_string(),
+        Some(c) if c.is_ascii_digit() => format!("_{name}"),
+        Some(_) => name,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::render_graphql;
+    use crate::SchemaHypothesis;
+    use crate::model::{AnyNode, NodeType, ObjectNode, ObjectProperty, StringNode};
+    use maplit::{btreemap, btreeset};
+    use serde_json::{Value, json};
+
+    fn hypothesis(documents: &[Value]) -> SchemaHypothesis {
+        documents.iter().fold(
+            SchemaHypothesis::new("id".to_string(), "order".to_string(), String::new()),
+            |hypothesis, document| hypothesis.merge(NodeType::from(document)),
+        )
+    }
+
+    #[test]
+    fn test_types() {
+        let hypothesis = hypothesis(&[
+            json!({"id": "f3fa7e18-549f-4ee1-8aeb-1bb8cbf7e956", "created-at": "2000-01-01T00:00:00Z", "items": [{"sku": "a", "count": 1.5}], "note": null}),
+            json!({"id": "f3fa7e18-549f-4ee1-8aeb-1bb8cbf7e956", "created-at": "2000-01-01T00:00:00Z", "items": [{"sku": "b", "count": 2.5}], "note": "fragile", "value": 1}),
+        ]);
+
+        let actual = render_graphql(&hypothesis);
+
+        assert_eq!(
+            actual,
+            r"scalar DateTime
+
+scalar UUID
+
+type Order {
+  created_at: DateTime!
+  id: UUID!
+  items: [ItemsItem!]!
+  note: String
+  value: Int
+}
+
+type ItemsItem {
+  count: Float!
+  sku: String!
+}"
+        );
+    }
+
+    #[test]
+    fn test_mixed_scalars() {
+        let hypothesis = hypothesis(&[json!({"value": 1}), json!({"value": "one"})]);
+
+        let actual = render_graphql(&hypothesis);
+
+        assert_eq!(actual, "scalar JSON\n\ntype Order {\n  value: JSON!\n}");
+    }
+
+    #[test]
+    fn test_object_union() {
+        let root = ObjectNode::new(btreemap! {
+            "payment".to_string() => ObjectProperty::new(AnyNode::new(btreeset![
+                ObjectNode::new(btreemap! {"iban".to_string() => ObjectProperty::new(StringNode::default())}).into(),
+                ObjectNode::new(btreemap! {"card".to_string() => ObjectProperty::new(StringNode::default())}).into(),
+            ]))
+        });
+        let hypothesis =
+            SchemaHypothesis::new("id".to_string(), "order".to_string(), String::new())
+                .merge(root.into());
+
+        let actual = render_graphql(&hypothesis);
+
+        assert_eq!(
+            actual,
+            r"type Order {
+  payment: Payment!
+}
+
+union Payment = Payment1 | Payment2
+
+type Payment2 {
+  iban: String!
+}
+
+type Payment1 {
+  card: String!
+}"
+        );
+    }
+
+    #[test]
+    fn test_names() {
+        let hypothesis =
+            hypothesis(&[json!({"a-b": 1, "a_b": 2, "query": {"text": "a"}, "date": {"day": 1}})]);
+
+        let actual = render_graphql(&hypothesis);
+
+        assert_eq!(
+            actual,
+            r"type Order {
+  a_b: Int!
+  a_b2: Int!
+  date: OrderDate!
+  query: OrderQuery!
+}
+
+type OrderQuery {
+  text: String!
+}
+
+type OrderDate {
+  day: Int!
+}"
+        );
+    }
+}
@@ -0,0 +1,586 @@
+//! generate Kotlin data classes or Java records with Jackson annotations from a [`SchemaHypothesis`]
+
+use crate::SchemaHypothesis;
+use crate::model::{NodeType, ObjectNode, StringFormat};
+use crate::naming::{Named, Names, object_union, pascal_case};
+use std::collections::{BTreeMap, BTreeSet};
+
+const KOTLIN_KEYWORDS: &[&str] = &[
+    "as",
+    "break",
+    "class",
+    "continue",
+    "do",
+    "else",
+    "false",
+    "for",
+    "fun",
+    "if",
+    "in",
+    "interface",
+    "is",
+    "null",
+    "object",
+    "package",
+    "return",
+    "super",
+    "this",
+    "throw",
+    "true",
+    "try",
+    "typealias",
+    "typeof",
+    "val",
+    "var",
+    "when",
+    "while",
+];
+
+const JAVA_KEYWORDS: &[&str] = &[
+    "abstract",
+    "assert",
+    "boolean",
+    "break",
+    "byte",
+    "case",
+    "catch",
+    "char",
+    "class",
+    "const",
+    "continue",
+    "default",
+    "do",
+    "double",
+    "else",
+    "enum",
+    "extends",
+    "false",
+    "final",
+    "finally",
+    "float",
+    "for",
+    "goto",
+    "if",
+    "implements",
+    "import",
+    "instanceof",
+    "int",
+    "interface",
+    "long",
+    "native",
+    "new",
+    "null",
+    "package",
+    "private",
+    "protected",
+    "public",
+    "return",
+    "short",
+    "static",
+    "strictfp",
+    "super",
+    "switch",
+    "synchronized",
+    "this",
+    "throw",
+    "throws",
+    "transient",
+    "true",
+    "try",
+    "void",
+    "volatile",
+    "while",
+];
+
+/// types used by the generated code, and `Object` (implicitly imported in both languages)
+const RESERVED: &[&str] = &[
+    "Boolean",
+    "Double",
+    "JsonNode",
+    "JsonProperty",
+    "JsonSubTypes",
+    "JsonTypeInfo",
+    "List",
+    "LocalDate",
+    "Long",
+    "Map",
+    "Object",
+    "OffsetDateTime",
+    "OffsetTime",
+    "String",
+    "UUID",
+];
+
+#[derive(Clone, Debug)]
+pub struct JvmOptions {
+    pub package: String,
+}
+
+impl Default for JvmOptions {
+    fn default() -> Self {
+        Self {
+            package: "schema".to_string(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Language {
+    Kotlin,
+    Java,
+}
+
+/// generate one Kotlin data class per object, unions of objects become sealed interfaces
+#[must_use]
+pub fn render_kotlin(schema: &SchemaHypothesis, options: &JvmOptions) -> String {
+    render(schema, options, Language::Kotlin)
+}
+
+/// generate one Java record per object (nested in an interface named after the title),
+/// unions of objects become sealed interfaces
+#[must_use]
+pub fn render_java(schema: &SchemaHypothesis, options: &JvmOptions) -> String {
+    render(schema, options, Language::Java)
+}
+
+struct Generator<'a> {
+    language: Language,
+    names: Names<'a>,
+    imports: BTreeSet<&'static str>,
+    /// the sealed interfaces each object implements
+    supertypes: BTreeMap<&'a ObjectNode, Vec<String>>,
+}
+
+fn render(schema: &SchemaHypothesis, options: &JvmOptions, language: Language) -> String {
+    let Some(root) = &schema.root else {
+        return format!("package {};\n", options.package);
+    };
+
+    let root_name = pascal_case(&schema.title);
+    // java nests the records into an interface named after the root
+    let container = format!("{root_name}Types");
+    let mut reserved = RESERVED.to_vec();
+    if language == Language::Java {
+        reserved.push(&container);
+    }
+    let names = Names::collect_reserving(root, &schema.title, &reserved);
+    let mut supertypes: BTreeMap<&ObjectNode, Vec<String>> = BTreeMap::new();
+    for (name, named) in &names.ordered {
+        if let Named::Union(objects) = named {
+            for object in objects {
+                supertypes.entry(*object).or_default().push(name.clone());
+            }
+        }
+    }
+
+    let mut generator = Generator {
+        language,
+        names,
+        imports: BTreeSet::new(),
+        supertypes,
+    };
+
+    let ordered = std::mem::take(&mut generator.names.ordered);
+    let mut declarations: Vec<String> = vec![];
+    // the root first, nested types after the types using them
+    for (name, named) in ordered.iter().rev() {
+        match named {
+            // objects without properties are maps, unless they are part of a sealed interface
+            Named::Object(object)
+                if object.properties.is_empty() && !generator.supertypes.contains_key(object) => {}
+            Named::Object(object) => declarations.push(generator.class(name, object)),
+            Named::Union(objects) => declarations.push(generator.sealed(name, objects)),
+        }
+    }
+
+    let imports: String = generator
+        .imports
+        .iter()
+        .map(|import| match language {
+            Language::Kotlin => format!("import {import}\n"),
+            Language::Java => format!("import {import};\n"),
+        })
+        .collect();
+
+    match language {
+        Language::Kotlin => {
+            if !root.is_object() {
+                let root_type = generator.jvm_type(root, true);
+                declarations.insert(0, format!("typealias {root_name} = {root_type}"));
+            }
+            format!(
+                "package {}\n\n{imports}\n{}\n",
+                options.package,
+                declarations.join("\n\n")
+            )
+        }
+        Language::Java => {
+            let container = if root.is_object() {
+                container
+            } else {
+                root_name
+            };
+            let declarations = declarations
+                .iter()
+                .map(|declaration| indent(declaration))
+                .collect::<Vec<_>>()
+                .join("\n\n");
+            format!(
+                "package {};\n\n{imports}\npublic interface {container} {{\n{declarations}\n}}\n",
+                options.package
+            )
+        }
+    }
+}
+
+impl Generator<'_> {
+    fn class(&mut self, name: &str, object: &ObjectNode) -> String {
+        if !object.properties.is_empty() {
+            self.imports
+                .insert("com.fasterxml.jackson.annotation.JsonProperty");
+        }
+
+        let mut parameter_names = BTreeSet::new();
+        let parameters: Vec<String> = object
+            .properties
+            .iter()
+            .map(|(key, property)| {
+                let mut name = camel_case(key);
+                let mut suffix = 2;
+                while !parameter_names.insert(name.clone()) {
+                    name = format!("{}{suffix}", camel_case(key));
+                    suffix += 1;
+                }
+                let name = self.escape(name);
+
+                let (node_type, nullable) = property.node_type.split_null();
+                let nullable = nullable || !property.required || node_type.is_none();
+                let jvm_type = match node_type {
+                    None => self.json_node(),
+                    Some(node_type) => self.jvm_type(node_type, nullable),
+                };
+                let key = serde_json::Value::String(key.clone());
+
+                match (self.language, nullable, property.required) {
+                    (Language::Kotlin, true, false) => {
+                        format!("    @JsonProperty({key}) val {name}: {jvm_type}? = null,")
+                    }
+                    (Language::Kotlin, true, true) => {
+                        format!("    @JsonProperty({key}) val {name}: {jvm_type}?,")
+                    }
+                    (Language::Kotlin, false, _) => {
+                        format!("    @JsonProperty({key}) val {name}: {jvm_type},")
+                    }
+                    (Language::Java, _, _) => format!("    @JsonProperty({key}) {jvm_type} {name}"),
+                }
+            })
+            .collect();
+
+        let supertypes = self.supertypes.get(object).cloned().unwrap_or_default();
+
+        match self.language {
+            Language::Kotlin => {
+                let supertypes = if supertypes.is_empty() {
+                    String::new()
+                } else {
+                    format!(" : {}", supertypes.join(", "))
+                };
+                if parameters.is_empty() {
+                    // data classes need parameters
+                    return format!("object {name}{supertypes}");
+                }
+                format!(
+                    "data class {name}(\n{}\n){supertypes}",
+                    parameters.join("\n")
+                )
+            }
+            Language::Java => {
+                let supertypes = if supertypes.is_empty() {
+                    String::new()
+                } else {
+                    format!(" implements {}", supertypes.join(", "))
+                };
+                if parameters.is_empty() {
+                    return format!("record {name}(){supertypes} {{}}");
+                }
+                format!(
+                    "record {name}(\n{}\n){supertypes} {{}}",
+                    parameters.join(",\n")
+                )
+            }
+        }
+    }
+
+    /// a sealed interface, deserialized by deducing the subtype from the properties present
+    fn sealed(&mut self, name: &str, objects: &[&ObjectNode]) -> String {
+        self.imports
+            .insert("com.fasterxml.jackson.annotation.JsonSubTypes");
+        self.imports
+            .insert("com.fasterxml.jackson.annotation.JsonTypeInfo");
+
+        let subtypes: Vec<&str> = objects
+            .iter()
+            .map(|object| self.names.object(object))
+            .collect();
+        let type_info = "@JsonTypeInfo(use = JsonTypeInfo.Id.DEDUCTION)";
+
+        match self.language {
+            Language::Kotlin => {
+                let types = subtypes
+                    .iter()
+                    .map(|subtype| format!("JsonSubTypes.Type({subtype}::class)"))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                format!("{type_info}\n@JsonSubTypes({types})\nsealed interface {name}")
+            }
+            Language::Java => {
+                let types = subtypes
+                    .iter()
+                    .map(|subtype| format!("@JsonSubTypes.Type({subtype}.class)"))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                format!(
+                    "{type_info}\n@JsonSubTypes({{{types}}})\nsealed interface {name} permits {} {{}}",
+                    subtypes.join(", ")
+                )
+            }
+        }
+    }
+
+    /// the type of a (non-null) value, `boxed` forces java to use wrapper-types instead of primitives
+    fn jvm_type(&mut self, node_type: &NodeType, boxed: bool) -> String {
+        let java = self.language == Language::Java;
+        match node_type {
+            NodeType::Boolean if java && !boxed => "boolean".to_string(),
+            NodeType::Integer(_) if java && !boxed => "long".to_string(),
+            NodeType::Number(_) if java && !boxed => "double".to_string(),
+            NodeType::Boolean => "Boolean".to_string(),
+            NodeType::Integer(_) => "Long".to_string(),
+            NodeType::Number(_) => "Double".to_string(),
+            NodeType::String(s) => {
+                let (import, name) = match s.format {
+                    None => return "String".to_string(),
+                    Some(StringFormat::DateTime) => ("java.time.OffsetDateTime", "OffsetDateTime"),
+                    Some(StringFormat::Date) => ("java.time.LocalDate", "LocalDate"),
+                    Some(StringFormat::Time) => ("java.time.OffsetTime", "OffsetTime"),
+                    Some(StringFormat::Uuid) => ("java.util.UUID", "UUID"),
+                };
+                self.imports.insert(import);
+                name.to_string()
+            }
+            NodeType::Array(array) => {
+                if java {
+                    self.imports.insert("java.util.List");
+                }
+                let items = match array.items.as_deref().map(NodeType::split_null) {
+                    None | Some((None, _)) => self.json_node(),
+                    Some((Some(items), nullable)) => {
+                        let items = self.jvm_type(items, true);
+                        if nullable && !java {
+                            format!("{items}?")
+                        } else {
+                            items
+                        }
+                    }
+                };
+                format!("List<{items}>")
+            }
+            NodeType::Object(object) if object.properties.is_empty() => {
+                if java {
+                    self.imports.insert("java.util.Map");
+                    format!("Map<String, {}>", self.json_node())
+                } else {
+                    format!("Map<String, {}?>", self.json_node())
+                }
+            }
+            NodeType::Object(object) => self.names.object(object).to_string(),
+            NodeType::Recursive(recursive) => self.names.recursive(recursive).to_string(),
+            NodeType::Any(any) if object_union(any).is_some() => self.names.union(any).to_string(),
+            NodeType::Null | NodeType::Any(_) => self.json_node(),
+        }
+    }
+
+    fn json_node(&mut self) -> String {
+        self.imports
+            .insert("com.fasterxml.jackson.databind.JsonNode");
+        "JsonNode".to_string()
+    }
+
+    /// escape names that are keywords of the language
+    fn escape(&self, mut name: String) -> String {
+        match self.language {
+            Language::Kotlin if KOTLIN_KEYWORDS.contains(&name.as_str()) => format!("`{name}`"),
+            Language::Java if JAVA_KEYWORDS.contains(&name.as_str()) => {
+                name.push('_');
+                name
+            }
+            _ => name,
+        }
+    }
+}
+
+/// `camelCase` of a property key, e.g. `created-at` becomes `createdAt`
+fn camel_case(key: &str) -> String {
+    let pascal = pascal_case(key);
+    pascal[..1].to_ascii_lowercase() + &pascal[1..]
+}
+
+fn indent(s: &str) -> String {
+    s.lines()
+        .map(|line| format!("    {line}"))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod test {
+    use super::{JvmOptions, camel_case, render_java, render_kotlin};
+    use crate::SchemaHypothesis;
+    use crate::model::{
+        AnyNode, IntegerNode, NodeType, ObjectNode, ObjectProperty, StringFormat, StringNode,
+    };
+    use maplit::{btreemap, btreeset};
+    use serde_json::{Value, json};
+    use test_case::test_case;
+
+    fn hypothesis(documents: &[Value]) -> SchemaHypothesis {
+        documents.iter().fold(
+            SchemaHypothesis::new("id".to_string(), "order".to_string(), String::new()),
+            |hypothesis, document| hypothesis.merge(NodeType::from(document)),
+        )
+    }
+
+    #[test_case("id", "id")]
+    #[test_case("created-at", "createdAt")]
+    #[test_case("user_id", "userId")]
+    #[test_case("@type", "type")]
+    fn test_camel_case(key: &str, expected: &str) {
+        assert_eq!(camel_case(key), expected);
+    }
+
+    #[test]
+    fn test_kotlin() {
+        let hypothesis = hypothesis(&[
+            json!({"id": 1, "created-at": "2000-01-01T00:00:00Z", "items": [{"sku": "a"}], "value": 1, "note": null, "class": "a"}),
+            json!({"id": 2, "created-at": "2000-01-01T00:00:00Z", "items": [{"sku": "b"}], "value": "one", "note": "fragile"}),
+        ]);
+
+        let actual = render_kotlin(&hypothesis, &JvmOptions::default());
+
+        assert_eq!(
+            actual,
+            r#"package schema
+
+import com.fasterxml.jackson.annotation.JsonProperty
+import com.fasterxml.jackson.databind.JsonNode
+import java.time.OffsetDateTime
+
+data class Order(
+    @JsonProperty("class") val `class`: String? = null,
+    @JsonProperty("created-at") val createdAt: OffsetDateTime,
+    @JsonProperty("id") val id: Long,
+    @JsonProperty("items") val items: List<ItemsItem>,
+    @JsonProperty("note") val note: String?,
+    @JsonProperty("value") val value: JsonNode,
+)
+
+data class ItemsItem(
+    @JsonProperty("sku") val sku: String,
+)
+"#
+        );
+    }
+
+    #[test]
+    fn test_java_sealed_interface() {
+        let root = ObjectNode::new(btreemap! {
+            "count".to_string() => ObjectProperty::new(IntegerNode::new()),
+            "payment".to_string() => ObjectProperty::new(AnyNode::new(btreeset![
+                ObjectNode::new(btreemap! {"iban".to_string() => ObjectProperty::new(StringNode::default())}).into(),
+                ObjectNode::new(btreemap! {
+                    "card".to_string() => ObjectProperty::new(StringNode::default()),
+                    "expires".to_string() => ObjectProperty::new(StringNode::new(Some(StringFormat::Date))),
+                }).into(),
+            ]))
+        });
+        let hypothesis =
+            SchemaHypothesis::new("id".to_string(), "order".to_string(), String::new())
+                .merge(root.into());
+
+        let actual = render_java(&hypothesis, &JvmOptions::default());
+
+        assert_eq!(
+            actual,
+            r#"package schema;
+
+import com.fasterxml.jackson.annotation.JsonProperty;
+import com.fasterxml.jackson.annotation.JsonSubTypes;
+import com.fasterxml.jackson.annotation.JsonTypeInfo;
+import java.time.LocalDate;
+
+public interface OrderTypes {
+    record Order(
+        @JsonProperty("count") long count,
+        @JsonProperty("payment") Payment payment
+    ) {}
+
+    @JsonTypeInfo(use = JsonTypeInfo.Id.DEDUCTION)
+    @JsonSubTypes({@JsonSubTypes.Type(Payment1.class), @JsonSubTypes.Type(Payment2.class)})
+    sealed interface Payment permits Payment1, Payment2 {}
+
+    record Payment2(
+        @JsonProperty("iban") String iban
+    ) implements Payment {}
+
+    record Payment1(
+        @JsonProperty("card") String card,
+        @JsonProperty("expires") LocalDate expires
+    ) implements Payment {}
+}
+"#
+        );
+    }
+
+    #[test]
+    fn test_sealed_interface_with_empty_object() {
+        let root = ObjectNode::new(btreemap! {
+            "payment".to_string() => ObjectProperty::new(AnyNode::new(btreeset![
+                ObjectNode::new(btreemap! {"iban".to_string() => ObjectProperty::new(StringNode::default())}).into(),
+                ObjectNode::new(btreemap! {}).into(),
+            ]))
+        });
+        let hypothesis =
+            SchemaHypothesis::new("id".to_string(), "order".to_string(), String::new())
+                .merge(root.into());
+
+        let actual = render_kotlin(&hypothesis, &JvmOptions::default());
+
+        assert_eq!(
+            actual,
+            r#"package schema
+
+import com.fasterxml.jackson.annotation.JsonProperty
+import com.fasterxml.jackson.annotation.JsonSubTypes
+import com.fasterxml.jackson.annotation.JsonTypeInfo
+
+data class Order(
+    @JsonProperty("payment") val payment: Payment,
+)
+
+@JsonTypeInfo(use = JsonTypeInfo.Id.DEDUCTION)
+@JsonSubTypes(JsonSubTypes.Type(Payment1::class), JsonSubTypes.Type(Payment2::class))
+sealed interface Payment
+
+data class Payment2(
+    @JsonProperty("iban") val iban: String,
+) : Payment
+
+object Payment1 : Payment
+"#
+        );
+        assert!(
+            render_java(&hypothesis, &JvmOptions::default())
+                .contains("    record Payment1() implements Payment {}\n")
+        );
+    }
+}
@@ -1,10 +1,44 @@
 #![allow(clippy::module_name_repetitions)]
 
+pub use arrow::{AnyFallback, ArrowOptions, arrow_schema, render_arrow_json, render_arrow_text};
+pub use decode::{PayloadFormat, decode_payload, read_document};
+pub use diff::{Change, ChangeKind, diff_hypotheses};
+pub use docs::{render_html, render_markdown};
+pub use embedded::EmbeddedJson;
+pub use error::{DecodeError, LoadError, ParseError, PathError, RenderError, SamplingError};
+pub use generate::HarvestOptions;
+pub use golang::{GoAnyType, GoOptions, render_go};
+pub use graphql::render_graphql;
+pub use jvm::{JvmOptions, render_java, render_kotlin};
 pub use model::SchemaHypothesis;
-pub use renderer::render_schema;
+pub use parse::parse_schema;
+pub use persistence::{HYPOTHESIS_FORMAT, HYPOTHESIS_VERSION, load_hypothesis, save_hypothesis};
+pub use pydantic::render_pydantic;
+pub use renderer::{JsonSchemaOptions, render_schema, render_schema_with_options};
+pub use sample::{Reservoir, Sampler, SamplingOptions, Stop, parse_duration};
+pub use select::{DocumentPath, Selection};
+pub use sql::{NestedObjects, SqlDialect, SqlOptions, render_sql};
 
+mod arrow;
+mod decode;
+mod diff;
+mod docs;
+mod embedded;
+mod error;
 mod format;
 mod generate;
+mod golang;
+mod graphql;
+mod jvm;
 mod merge;
 pub mod model;
+mod naming;
+mod observe;
+mod parse;
+mod persistence;
+mod pydantic;
+mod recursion;
 mod renderer;
+mod sample;
+mod select;
+mod sql;
@@ -1,38 +1,30 @@
-use crate::merge;
+use crate::merge::merge_into;
+use crate::merge::string::mergeable;
 use crate::model::{AnyNode, NodeType};
+use std::mem::discriminant;
 
-pub fn merge_any(xs: &AnyNode, ys: AnyNode) -> NodeType {
-    let xs = &xs.nodes;
-    let ys = ys.nodes;
+/// merge `ys` into `xs`, objects, arrays and embedded documents are merged with the one already present
+pub fn merge_any_into(xs: &mut AnyNode, ys: AnyNode) -> bool {
+    let mut nodes: Vec<NodeType> = std::mem::take(&mut xs.nodes).into_iter().collect();
+    let mut changed = false;
 
-    let mut zs = xs.clone();
-    for node_type in ys {
-        match node_type {
-            node @ NodeType::Object { .. } => match xs.iter().find(|x| x.is_object()) {
-                None => {
-                    zs.insert(node);
-                }
-                Some(other @ NodeType::Object { .. }) => {
-                    zs.remove(other);
-                    zs.insert(merge::merge_node_type(node, other.clone()));
-                }
-                Some(_) => unreachable!(),
-            },
-            node @ NodeType::Array(_) => match xs.iter().find(|x| x.is_array()) {
-                None => {
-                    zs.insert(node);
-                }
-                Some(other @ NodeType::Array(_)) => {
-                    zs.remove(other);
-                    zs.insert(merge::merge_node_type(node, other.clone()));
-                }
-                Some(_) => unreachable!(),
-            },
-            _ => {
-                zs.insert(node_type);
-            }
+    for node in ys.nodes {
+        if let Some(existing) = nodes.iter_mut().find(|x| merges_with(x, &node)) {
+            changed |= merge_into(existing, node);
+        } else if !nodes.contains(&node) {
+            nodes.push(node);
+            changed = true;
         }
     }
 
-    AnyNode::new(zs).into()
+    xs.nodes = nodes.into_iter().collect();
+    changed
+}
+
+/// whether `node` is merged into `x` rather than added to the union
+fn merges_with(x: &NodeType, node: &NodeType) -> bool {
+    match (x, node) {
+        (NodeType::String(x), NodeType::String(y)) => x.content.is_some() && mergeable(x, y),
+        (x, node) => (node.is_object() || node.is_array()) && discriminant(x) == discriminant(node),
+    }
 }
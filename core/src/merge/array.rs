@@ -1,12 +1,13 @@
-use crate::merge::merge_node_type;
+use crate::merge::merge_into;
 use crate::model::ArrayNode;
 
-pub fn merge_array(a: ArrayNode, b: ArrayNode) -> ArrayNode {
-    match (a, b) {
-        (ArrayNode { items: None }, ys) => ys,
-        (xs, ArrayNode { items: None }) => xs,
-        (ArrayNode { items: Some(xs) }, ArrayNode { items: Some(ys) }) => {
-            ArrayNode::new(merge_node_type(*xs, *ys))
+pub fn merge_array_into(a: &mut ArrayNode, b: ArrayNode) -> bool {
+    match (&mut a.items, b.items) {
+        (_, None) => false,
+        (None, ys) => {
+            a.items = ys;
+            true
         }
+        (Some(xs), Some(ys)) => merge_into(xs, *ys),
     }
 }
@@ -1,6 +1,9 @@
-use crate::merge::array::merge_array;
-use crate::merge::object::merge_object;
-use crate::model::{AnyNode, NodeType, SchemaHypothesis};
+use crate::generate::HarvestOptions;
+use crate::merge::any::merge_any_into;
+use crate::merge::array::merge_array_into;
+use crate::merge::object::merge_object_into;
+use crate::model::{AnyNode, NodeType, ObjectNode, SchemaHypothesis};
+use crate::recursion::{change_folded, fold_recursion_with};
 use maplit::btreeset;
 
 mod any;
@@ -11,36 +14,100 @@ mod string;
 
 impl SchemaHypothesis {
     #[must_use]
-    pub fn merge(self, other_root: NodeType) -> SchemaHypothesis {
-        let root = if let Some(root) = self.root {
-            merge_node_type(root, other_root)
-        } else {
-            other_root
+    pub fn merge(mut self, other_root: NodeType) -> SchemaHypothesis {
+        self.merge_in_place(other_root);
+        self
+    }
+
+    /// merge the given node into the hypothesis, returns whether the hypothesis changed
+    ///
+    /// Cheaper than [`SchemaHypothesis::merge`] followed by comparing with the previous hypothesis.
+    pub fn merge_in_place(&mut self, other_root: NodeType) -> bool {
+        self.merge_in_place_with(other_root, &HarvestOptions::default())
+    }
+
+    /// like [`SchemaHypothesis::merge_in_place`], with options (of which `no_recursion` applies)
+    pub fn merge_in_place_with(&mut self, other_root: NodeType, options: &HarvestOptions) -> bool {
+        match &mut self.root {
+            None => {
+                let mut root = other_root;
+                fold_recursion_with(&mut root, options);
+                self.root = Some(root);
+                true
+            }
+            Some(root) => change_folded(root, options, |root| merge_into(root, other_root)),
+        }
+    }
+
+    /// merge two complete hypotheses, e.g. harvested by parallel workers or from different partitions
+    ///
+    /// The metadata (`id`, `title` and `description`) of `self` wins, empty values are taken from `other`.
+    #[must_use]
+    pub fn merge_hypothesis(self, other: SchemaHypothesis) -> SchemaHypothesis {
+        self.merge_hypothesis_with(other, &HarvestOptions::default())
+    }
+
+    /// like [`SchemaHypothesis::merge_hypothesis`], with options (of which `no_recursion` applies)
+    #[must_use]
+    pub fn merge_hypothesis_with(
+        self,
+        other: SchemaHypothesis,
+        options: &HarvestOptions,
+    ) -> SchemaHypothesis {
+        let or_other = |value: String, other: String| if value.is_empty() { other } else { value };
+
+        let root = match (self.root, other.root) {
+            (Some(a), Some(b)) => {
+                let mut root = merge_node_type(a, b);
+                fold_recursion_with(&mut root, options);
+                Some(root)
+            }
+            (a, b) => a.or(b),
         };
 
         SchemaHypothesis {
-            id: self.id,
-            title: self.title,
-            description: self.description,
-            root: Some(root),
+            id: or_other(self.id, other.id),
+            title: or_other(self.title, other.title),
+            description: or_other(self.description, other.description),
+            root,
         }
     }
 }
 
-pub fn merge_node_type(a: NodeType, b: NodeType) -> NodeType {
-    match (a, b) {
-        (a, b) if a == b => a,
-        (NodeType::String(a), NodeType::String(b)) => string::merge(a, b),
-        (NodeType::Object(a), NodeType::Object(b)) => merge_object(a, b).into(),
-        (NodeType::Array(a), NodeType::Array(b)) => merge_array(a, b).into(),
-        (NodeType::Any(xs), NodeType::Any(ys)) => any::merge_any(&xs, ys),
-        (a @ NodeType::Any(_), b) | (b, a @ NodeType::Any(_)) => {
-            merge_node_type(a, AnyNode::new(btreeset![b]).into())
+pub fn merge_node_type(mut a: NodeType, b: NodeType) -> NodeType {
+    merge_into(&mut a, b);
+    a
+}
+
+pub(crate) fn merge_object(mut a: ObjectNode, b: ObjectNode) -> ObjectNode {
+    merge_object_into(&mut a, b);
+    a
+}
+
+/// merge `b` into `a`, returns whether `a` changed
+pub fn merge_into(a: &mut NodeType, b: NodeType) -> bool {
+    match (&mut *a, b) {
+        (NodeType::Object(xs), NodeType::Object(ys)) => merge_object_into(xs, ys),
+        (NodeType::Array(xs), NodeType::Array(ys)) => merge_array_into(xs, ys),
+        (NodeType::Any(xs), NodeType::Any(ys)) => merge_any_into(xs, ys),
+        (NodeType::Any(xs), b) => merge_any_into(xs, AnyNode::new(btreeset![b])),
+        (NodeType::String(xs), NodeType::String(ys)) => {
+            let merged = string::merge(xs.clone(), ys);
+            let changed = merged != *a;
+            *a = merged;
+            changed
+        }
+        (x, b) if *x == b => false,
+        (x, b) => {
+            let ys = match b {
+                NodeType::Any(ys) => ys,
+                b => AnyNode::new(btreeset![b]),
+            };
+            let mut xs = AnyNode::new(btreeset![std::mem::replace(x, NodeType::Null)]);
+            merge_any_into(&mut xs, ys);
+            *a = xs.into();
+            true
         }
-        (a, b) => merge_node_type(
-            AnyNode::new(btreeset![a]).into(),
-            AnyNode::new(btreeset![b]).into(),
-        ),
     }
 }
 
@@ -48,11 +115,12 @@ pub fn merge_node_type(a: NodeType, b: NodeType) -> NodeType {
 mod test {
     use maplit::{btreemap, btreeset};
 
-    use crate::merge::merge_node_type;
+    use crate::merge::{merge_into, merge_node_type};
     use crate::model::{
-        AnyNode, ArrayNode, IntegerNode, NodeType, ObjectNode, ObjectProperty, StringFormat,
-        StringNode,
+        AnyNode, ArrayNode, ContentEncoding, IntegerNode, NodeType, ObjectNode, ObjectProperty,
+        SchemaHypothesis, StringFormat, StringNode,
     };
+    use serde_json::json;
 
     #[test]
     fn test_merge_string() {
@@ -101,6 +169,26 @@ mod test {
         assert_eq!(actual, StringNode::default().into());
     }
 
+    #[test]
+    fn test_merge_string_with_embedded_documents() {
+        let embedded =
+            |encoding, document| StringNode::embedded(encoding, NodeType::from(&document)).into();
+        let a = embedded(None, json!({"id": 1}));
+        let b = embedded(None, json!({"id": "a", "note": null}));
+        let c = embedded(Some(ContentEncoding::Base64), json!([1]));
+
+        let actual = merge_node_type(merge_node_type(a, b), c.clone());
+
+        let merged = merge_node_type(
+            NodeType::from(&json!({"id": 1})),
+            NodeType::from(&json!({"id": "a", "note": null})),
+        );
+        assert_eq!(
+            actual,
+            AnyNode::new(btreeset![StringNode::embedded(None, merged).into(), c]).into()
+        );
+    }
+
     #[test]
     fn test_merge_array_without_types() {
         let a = ArrayNode::default();
@@ -308,4 +396,94 @@ mod test {
             .into()
         );
     }
+
+    #[test]
+    fn test_merge_hypothesis() {
+        let a = SchemaHypothesis::new("a".to_string(), "orders".to_string(), String::new())
+            .merge(NodeType::from(&json!({"id": 1})));
+        let b = SchemaHypothesis::new("b".to_string(), String::new(), "all orders".to_string())
+            .merge(NodeType::from(&json!({"id": 2, "note": "x"})));
+
+        let actual = a.merge_hypothesis(b);
+
+        assert_eq!(
+            actual,
+            SchemaHypothesis::new(
+                "a".to_string(),
+                "orders".to_string(),
+                "all orders".to_string()
+            )
+            .merge(NodeType::from(&json!({"id": 1})))
+            .merge(NodeType::from(&json!({"id": 2, "note": "x"})))
+        );
+    }
+
+    #[test]
+    fn test_merge_hypothesis_empty() {
+        let empty = SchemaHypothesis::new("a".to_string(), "orders".to_string(), String::new());
+        let other = SchemaHypothesis::new("b".to_string(), "orders".to_string(), String::new())
+            .merge(NodeType::from(&json!({"id": 1})));
+
+        assert_eq!(empty.clone().merge_hypothesis(empty.clone()), empty);
+        assert_eq!(
+            empty.clone().merge_hypothesis(other.clone()).root,
+            other.clone().root
+        );
+        assert_eq!(other.clone().merge_hypothesis(empty).root, other.root);
+    }
+
+    #[test]
+    fn test_merge_in_place() {
+        let mut hypothesis =
+            SchemaHypothesis::new("id".to_string(), "order".to_string(), String::new());
+
+        assert!(hypothesis.merge_in_place(NodeType::from(&json!({"id": 1, "tags": []}))));
+        assert!(!hypothesis.merge_in_place(NodeType::from(&json!({"id": 2, "tags": []}))));
+        assert!(hypothesis.merge_in_place(NodeType::from(&json!({"id": 3, "tags": ["a"]}))));
+        assert!(!hypothesis.merge_in_place(NodeType::from(&json!({"id": 4, "tags": []}))));
+        assert!(hypothesis.merge_in_place(NodeType::from(&json!({"id": 5}))));
+        assert!(!hypothesis.merge_in_place(NodeType::from(&json!({"id": 6, "tags": ["b"]}))));
+        assert!(hypothesis.merge_in_place(NodeType::from(&json!({"id": "7"}))));
+        assert!(!hypothesis.merge_in_place(NodeType::from(&json!({"id": 8}))));
+    }
+
+    #[test]
+    fn test_merge_into_changed() {
+        let cases = [
+            (json!(1), json!(2), false),
+            (json!(1), json!("a"), true),
+            (json!("2000-01-01"), json!("a"), true),
+            (json!("a"), json!("2000-01-01"), false),
+            (json!({"a": 1, "b": null}), json!({"a": 1}), true),
+            (json!({"a": 1}), json!({"a": 2, "b": null}), true),
+            (json!([1, "a"]), json!(["b"]), false),
+            (json!([1, "a"]), json!([true]), true),
+            (json!([{"a": 1}, 1]), json!([{"a": 2}]), false),
+            (json!([{"a": 1}, 1]), json!([{"b": 2}]), true),
+        ];
+
+        for (a, b, expected) in cases {
+            let mut actual = NodeType::from(&a);
+            let changed = merge_into(&mut actual, NodeType::from(&b));
+
+            assert_eq!(changed, expected, "{a} ∪ {b}");
+            assert_eq!(changed, actual != NodeType::from(&a), "{a} ∪ {b}");
+        }
+    }
+
+    #[test]
+    fn test_merge_in_place_deeper_recursion() {
+        let mut hypothesis =
+            SchemaHypothesis::new("id".to_string(), "node".to_string(), String::new());
+        hypothesis.merge_in_place((&json!({"id": 1, "next": {"id": 2, "next": null}})).into());
+        let before = hypothesis.clone();
+
+        // deeper levels are folded into the known recursion again
+        let changed = hypothesis.merge_in_place(
+            (&json!({"id": 1, "next": {"id": 2, "next": {"id": 3, "next": null}}})).into(),
+        );
+
+        assert!(!changed);
+        assert_eq!(hypothesis, before);
+    }
 }
@@ -1,27 +1,28 @@
-use crate::merge::object_property;
+use crate::merge::object_property::merge_object_property_into;
 use crate::model::ObjectNode;
-use std::collections::HashSet;
 
-pub fn merge_object(a: ObjectNode, b: ObjectNode) -> ObjectNode {
-    let properties_a = a.properties;
-    let properties_b = b.properties;
+pub fn merge_object_into(a: &mut ObjectNode, b: ObjectNode) -> bool {
+    let mut others = b.properties;
+    let mut changed = false;
 
-    let keys_a: HashSet<&String> = properties_a.keys().collect();
-    let keys_b: HashSet<&String> = properties_b.keys().collect();
-    let merged_properties = keys_a
-        .union(&keys_b)
-        .map(|key| {
-            (
-                (*key).to_string(),
-                object_property::merge_object_property(
-                    properties_a.get(*key),
-                    properties_b.get(*key),
-                ),
-            )
-        })
-        .collect();
+    for (key, property) in &mut a.properties {
+        match others.remove(key) {
+            Some(other) => changed |= merge_object_property_into(property, other),
+            // missing in the other object
+            None if property.required => {
+                property.required = false;
+                changed = true;
+            }
+            None => {}
+        }
+    }
 
-    ObjectNode {
-        properties: merged_properties,
+    // missing in this object
+    for (key, mut property) in others {
+        property.required = false;
+        a.properties.insert(key, property);
+        changed = true;
     }
+
+    changed
 }
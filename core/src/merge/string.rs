@@ -1,13 +1,27 @@
+use crate::merge::merge_into;
 use crate::model::{AnyNode, NodeType, StringNode};
 use maplit::btreeset;
 
 pub fn merge(a: StringNode, b: StringNode) -> NodeType {
-    match (&a.format, &b.format) {
+    match (a, b) {
         // if one is more specific than the other… just drop the type :/
-        (Some(_), None) | (None, Some(_)) => StringNode::default().into(),
-        // if they're the same, keep it
-        (None, None) => StringNode::default().into(),
-        (Some(a), Some(b)) if a == b => StringNode::new(Some(a.to_owned())).into(),
-        (Some(_), Some(_)) => AnyNode::new(btreeset![a.into(), b.into()]).into(),
+        (a, b) if a.is_plain() || b.is_plain() => StringNode::default().into(),
+        (mut a, b) if mergeable(&a, &b) => {
+            if let (Some(xs), Some(ys)) = (&mut a.content, b.content) {
+                merge_into(&mut xs.schema, *ys.schema);
+            }
+            a.into()
+        }
+        (a, b) => AnyNode::new(btreeset![a.into(), b.into()]).into(),
+    }
+}
+
+/// whether two strings merge into one string (rather than a union), i.e. have the same format or both
+/// embed a document with the same encoding
+pub fn mergeable(a: &StringNode, b: &StringNode) -> bool {
+    match (&a.content, &b.content) {
+        (Some(xs), Some(ys)) => xs.encoding == ys.encoding,
+        (None, None) => a.format == b.format,
+        _ => false,
     }
 }
@@ -1,9 +1,11 @@
 use crate::model::any::AnyNode;
 use crate::model::node_type::NodeType;
+use serde::{Deserialize, Serialize};
 use std::collections::BTreeSet;
 
-#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Default)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Default, Serialize, Deserialize)]
 pub struct ArrayNode {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub items: Option<Box<NodeType>>,
 }
 
@@ -1,4 +1,6 @@
-#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Default)]
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Default, Serialize, Deserialize)]
 pub struct IntegerNode {}
 
 impl IntegerNode {
@@ -4,9 +4,10 @@ pub use integer::IntegerNode;
 pub use node_type::NodeType;
 pub use number::NumberNode;
 pub use object::{ObjectNode, ObjectProperty};
+pub use recursive::RecursiveNode;
 pub use schema::SchemaHypothesis;
 pub use string::Format as StringFormat;
-pub use string::StringNode;
+pub use string::{ContentEncoding, StringContent, StringNode};
 
 mod any;
 mod array;
@@ -14,5 +15,6 @@ mod integer;
 mod node_type;
 mod number;
 mod object;
+mod recursive;
 mod schema;
 mod string;
@@ -3,12 +3,15 @@ use crate::model::array::ArrayNode;
 use crate::model::integer::IntegerNode;
 use crate::model::number::NumberNode;
 use crate::model::object::ObjectNode;
+use crate::model::recursive::RecursiveNode;
 use crate::model::string::StringNode;
+use serde::{Deserialize, Serialize};
 
 /// each "node" in a JSON-document we detect is either a
 /// JSON schema [basic type](https://json-schema.org/understanding-json-schema/reference/type)
 /// or a [composition](https://json-schema.org/understanding-json-schema/reference/combining)
-#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
 pub enum NodeType {
     /// "OR" composition: Must be valid against any of the subschemas
     Any(AnyNode),
@@ -18,6 +21,8 @@ pub enum NodeType {
     Null,
     Number(NumberNode),
     Object(ObjectNode),
+    /// reference to an enclosing object
+    Recursive(RecursiveNode),
     String(StringNode),
 }
 
@@ -31,6 +36,27 @@ impl NodeType {
     pub fn is_array(&self) -> bool {
         matches!(self, NodeType::Array(_))
     }
+
+    /// separate `null` from the actual type of a node.
+    ///
+    /// Returns the type (if there is anything besides `null`) and whether the node is nullable.
+    /// Unions of multiple non-null types are returned as they are.
+    #[must_use]
+    pub fn split_null(&self) -> (Option<&NodeType>, bool) {
+        match self {
+            NodeType::Null => (None, true),
+            NodeType::Any(any) => {
+                let nullable = any.nodes.contains(&NodeType::Null);
+                let mut others = any.nodes.iter().filter(|n| **n != NodeType::Null);
+                match (others.next(), others.next()) {
+                    (None, _) => (None, nullable),
+                    (Some(node_type), None) => (Some(node_type), nullable),
+                    (Some(_), Some(_)) => (Some(self), nullable),
+                }
+            }
+            node_type => (Some(node_type), false),
+        }
+    }
 }
 
 impl From<StringNode> for NodeType {
@@ -63,6 +89,12 @@ impl From<ObjectNode> for NodeType {
     }
 }
 
+impl From<RecursiveNode> for NodeType {
+    fn from(r: RecursiveNode) -> Self {
+        NodeType::Recursive(r)
+    }
+}
+
 impl From<AnyNode> for NodeType {
     fn from(a: AnyNode) -> Self {
         NodeType::Any(a)
@@ -0,0 +1,16 @@
+use serde::{Deserialize, Serialize};
+
+/// a reference to an enclosing object, for recursive structures like trees or comment threads
+///
+/// `depth` counts the enclosing objects, i.e. `1` references the object the property belongs to, `2` its parent etc.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct RecursiveNode {
+    pub depth: usize,
+}
+
+impl RecursiveNode {
+    #[must_use]
+    pub fn new(depth: usize) -> Self {
+        Self { depth }
+    }
+}
@@ -1,6 +1,7 @@
 use crate::model::NodeType;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub struct SchemaHypothesis {
     pub id: String,
     pub title: String,
@@ -1,15 +1,64 @@
 use crate::format::{is_valid_date, is_valid_datetime, is_valid_time, is_valid_uuid};
+use crate::model::NodeType;
+use serde::{Deserialize, Serialize};
 use strum_macros;
-use strum_macros::IntoStaticStr;
+use strum_macros::{EnumString, IntoStaticStr};
 
-#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Default)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Default, Serialize, Deserialize)]
 pub struct StringNode {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub format: Option<Format>,
+    /// a JSON document embedded into the string
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content: Option<StringContent>,
+}
+
+/// JSON document embedded into a string, rendered as `contentMediaType` and `contentSchema`
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct StringContent {
+    /// how the document is encoded, `None` for the plain JSON text
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub encoding: Option<ContentEncoding>,
+    pub schema: Box<NodeType>,
+}
+
+/// encoding of embedded documents, see https://json-schema.org/understanding-json-schema/reference/non_json_data#contentencoding
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    PartialEq,
+    Eq,
+    Hash,
+    PartialOrd,
+    Ord,
+    IntoStaticStr,
+    EnumString,
+    Serialize,
+    Deserialize,
+)]
+#[strum(serialize_all = "kebab-case")]
+#[serde(rename_all = "kebab-case")]
+pub enum ContentEncoding {
+    Base64,
 }
 
 /// string format, see https://www.learnjsonschema.com/2020-12/format-annotation/format/
-#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, IntoStaticStr)]
+#[derive(
+    Clone,
+    Debug,
+    PartialEq,
+    Eq,
+    Hash,
+    PartialOrd,
+    Ord,
+    IntoStaticStr,
+    EnumString,
+    Serialize,
+    Deserialize,
+)]
 #[strum(serialize_all = "kebab-case")]
+#[serde(rename_all = "kebab-case")]
 pub enum Format {
     DateTime,
     Date,
@@ -35,15 +84,34 @@ pub enum Format {
 impl StringNode {
     #[must_use]
     pub fn new(format: Option<Format>) -> Self {
-        Self { format }
+        Self {
+            format,
+            content: None,
+        }
     }
 
     #[must_use]
     pub fn formatted(format: Format) -> Self {
+        Self::new(Some(format))
+    }
+
+    /// a string containing a JSON document of the given schema
+    #[must_use]
+    pub fn embedded(encoding: Option<ContentEncoding>, schema: NodeType) -> Self {
         Self {
-            format: Some(format),
+            format: None,
+            content: Some(StringContent {
+                encoding,
+                schema: Box::new(schema),
+            }),
         }
     }
+
+    /// whether the string may be any string, i.e. has neither format nor content
+    #[must_use]
+    pub fn is_plain(&self) -> bool {
+        self.format.is_none() && self.content.is_none()
+    }
 }
 
 impl From<&str> for StringNode {
@@ -77,7 +145,7 @@ mod test {
     #[test]
     fn test_string_node() {
         let sample: StringNode = "test".into();
-        let expected = StringNode { format: None };
+        let expected = StringNode::default();
         assert_eq!(sample, expected);
     }
 
@@ -0,0 +1,276 @@
+//! deterministic names for the objects (and unions of objects) of a hypothesis, used by code-generators
+//!
+//! Names are derived from the property path: an object in property `address` becomes `Address`, items of
+//! an array in property `tags` become `TagsItem`. If a name is already taken by a different object, the
+//! name of the parent is prepended (e.g. `OrderAddress`). Structurally identical objects share one name. Names
+//! reserved by the target language (e.g. builtin types) are never taken.
+
+use crate::model::{AnyNode, NodeType, ObjectNode, RecursiveNode};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// a named type to generate code for
+pub(crate) enum Named<'a> {
+    Object(&'a ObjectNode),
+    /// union of multiple objects
+    Union(Vec<&'a ObjectNode>),
+}
+
+#[derive(Default)]
+pub(crate) struct Names<'a> {
+    objects: BTreeMap<&'a ObjectNode, String>,
+    unions: BTreeMap<&'a AnyNode, String>,
+    /// references are only meaningful relative to their position, so they're identified by address
+    recursions: BTreeMap<*const RecursiveNode, String>,
+    /// references to each object currently visited, resolved once the object has got its name
+    enclosing: Vec<Vec<*const RecursiveNode>>,
+    taken: BTreeSet<String>,
+    /// all named types, nested types before the types using them
+    pub(crate) ordered: Vec<(String, Named<'a>)>,
+}
+
+impl<'a> Names<'a> {
+    /// collect names for the given root, `root_name` is used for the root (if it is an object)
+    ///
+    /// If the root is not an object, `root_name` is reserved, e.g. for an alias of the root type.
+    pub(crate) fn collect(root: &'a NodeType, root_name: &str) -> Self {
+        Self::collect_reserving(root, root_name, &[])
+    }
+
+    /// like [`Names::collect`], never taking the `reserved` names (e.g. builtin types of the target language)
+    pub(crate) fn collect_reserving(
+        root: &'a NodeType,
+        root_name: &str,
+        reserved: &[&str],
+    ) -> Self {
+        let root_name = pascal_case(root_name);
+        let mut names = Names {
+            taken: reserved.iter().map(ToString::to_string).collect(),
+            ..Names::default()
+        };
+        if !root.is_object() {
+            names.taken.insert(root_name.clone());
+        }
+        names.visit(root, &root_name, "");
+        names
+    }
+
+    pub(crate) fn object(&self, object: &ObjectNode) -> &str {
+        &self.objects[object]
+    }
+
+    pub(crate) fn union(&self, any: &AnyNode) -> &str {
+        &self.unions[any]
+    }
+
+    /// the name of the object referenced
+    pub(crate) fn recursive(&self, recursive: &RecursiveNode) -> &str {
+        &self.recursions[&std::ptr::from_ref(recursive)]
+    }
+
+    fn visit(&mut self, node_type: &'a NodeType, name: &str, parent: &str) {
+        match node_type {
+            NodeType::Object(object) => self.visit_object(object, name, parent),
+            NodeType::Array(array) => {
+                if let Some(items) = &array.items {
+                    self.visit(items, &format!("{name}Item"), parent);
+                }
+            }
+            NodeType::Any(any) => match object_union(any) {
+                Some(objects) => {
+                    for (i, object) in objects.iter().enumerate() {
+                        self.visit_object(object, &format!("{name}{}", i + 1), parent);
+                    }
+                    if !self.unions.contains_key(any) {
+                        let name = self.reserve(name, parent);
+                        self.unions.insert(any, name.clone());
+                        self.ordered.push((name, Named::Union(objects)));
+                    }
+                }
+                None => {
+                    for node_type in &any.nodes {
+                        self.visit(node_type, name, parent);
+                    }
+                }
+            },
+            NodeType::Recursive(recursive) => {
+                let index = self.enclosing.len().checked_sub(recursive.depth);
+                if let Some(references) = index.and_then(|index| self.enclosing.get_mut(index)) {
+                    references.push(std::ptr::from_ref(recursive));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn visit_object(&mut self, object: &'a ObjectNode, name: &str, parent: &str) {
+        self.enclosing.push(vec![]);
+        for (key, property) in &object.properties {
+            self.visit(&property.node_type, &pascal_case(key), name);
+        }
+        let references = self.enclosing.pop().unwrap_or_default();
+
+        let name = match self.objects.get(object) {
+            Some(name) => name.clone(),
+            None => {
+                let name = self.reserve(name, parent);
+                self.objects.insert(object, name.clone());
+                self.ordered.push((name.clone(), Named::Object(object)));
+                name
+            }
+        };
+        for reference in references {
+            self.recursions.insert(reference, name.clone());
+        }
+    }
+
+    fn reserve(&mut self, name: &str, parent: &str) -> String {
+        let mut candidate = name.to_string();
+        if self.taken.contains(&candidate) {
+            candidate = format!("{parent}{name}");
+        }
+        let mut suffix = 2;
+        while self.taken.contains(&candidate) {
+            candidate = format!("{parent}{name}{suffix}");
+            suffix += 1;
+        }
+        self.taken.insert(candidate.clone());
+        candidate
+    }
+}
+
+/// the objects of an [`AnyNode`], if it consists of more than one object (and optionally `null`) only
+pub(crate) fn object_union(any: &AnyNode) -> Option<Vec<&ObjectNode>> {
+    let objects: Vec<_> = any
+        .nodes
+        .iter()
+        .filter_map(|node_type| match node_type {
+            NodeType::Object(object) => Some(object),
+            _ => None,
+        })
+        .collect();
+
+    let only_objects = any
+        .nodes
+        .iter()
+        .all(|node_type| matches!(node_type, NodeType::Object(_) | NodeType::Null));
+
+    (only_objects && objects.len() > 1).then_some(objects)
+}
+
+/// `PascalCase` of an arbitrary string, dropping all characters that are not alphanumeric
+pub(crate) fn pascal_case(s: &str) -> String {
+    let mut result = String::new();
+    let mut upper_next = true;
+    for c in s.chars() {
+        if c.is_ascii_alphanumeric() {
+            if upper_next {
+                result.push(c.to_ascii_uppercase());
+            } else {
+                result.push(c);
+            }
+            upper_next = false;
+        } else {
+            upper_next = true;
+        }
+    }
+
+    match result.chars().next() {
+        None => "Root".to_string(),
+        Some(c) if c.is_ascii_digit() => format!("T{result}"),
+        Some(_) => result,
+    }
+}
+
+/// `snake_case` of an arbitrary (camelCase, kebab-case, …) string, dropping all characters that are not alphanumeric
+pub(crate) fn snake_case(s: &str) -> String {
+    let mut result = String::new();
+    let mut previous_lower = false;
+    for c in s.chars() {
+        if c.is_ascii_alphanumeric() {
+            if c.is_ascii_uppercase() && previous_lower {
+                result.push('_');
+            }
+            previous_lower = c.is_ascii_lowercase() || c.is_ascii_digit();
+            result.push(c.to_ascii_lowercase());
+        } else {
+            if !result.is_empty() && !result.ends_with('_') {
+                result.push('_');
+            }
+            previous_lower = false;
+        }
+    }
+
+    result.trim_end_matches('_').to_string()
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Names, pascal_case, snake_case};
+    use crate::model::NodeType;
+    use serde_json::json;
+    use test_case::test_case;
+
+    #[test_case("address", "Address")]
+    #[test_case("shipping_address", "ShippingAddress")]
+    #[test_case("shippingAddress", "ShippingAddress")]
+    #[test_case("@type", "Type")]
+    #[test_case("1st", "T1st")]
+    #[test_case("", "Root")]
+    fn test_pascal_case(input: &str, expected: &str) {
+        assert_eq!(pascal_case(input), expected);
+    }
+
+    #[test_case("address", "address")]
+    #[test_case("shippingAddress", "shipping_address")]
+    #[test_case("Shipping-Address", "shipping_address")]
+    #[test_case("@type", "type")]
+    fn test_snake_case(input: &str, expected: &str) {
+        assert_eq!(snake_case(input), expected);
+    }
+
+    #[test]
+    fn test_names() {
+        let root = NodeType::from(&json!({
+            "billing": {"address": {"street": "Main"}},
+            "shipping": {"address": {"street": "Main", "zip": 1}},
+            "items": [{"sku": "a"}],
+            "copy": {"street": "Main"}
+        }));
+
+        let names = Names::collect(&root, "order");
+        let actual: Vec<_> = names
+            .ordered
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect();
+
+        assert_eq!(
+            actual,
+            vec![
+                "Address",
+                "Billing",
+                "ItemsItem",
+                "ShippingAddress",
+                "Shipping",
+                "Order"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_reserved_names() {
+        let root = NodeType::from(&json!([{"string": {"a": 1}, "order": {"b": 2}}]));
+
+        let names = Names::collect_reserving(&root, "order", &["String"]);
+        let actual: Vec<_> = names
+            .ordered
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect();
+
+        assert_eq!(
+            actual,
+            vec!["OrderItemOrder", "OrderItemString", "OrderItem"]
+        );
+    }
+}
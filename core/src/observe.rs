@@ -0,0 +1,565 @@
+//! merge documents directly into a hypothesis, without generating a [`NodeType`] tree per document first
+//!
+//! The document is walked along the hypothesis, only where it doesn't fit (i.e. the hypothesis widens) nodes are
+//! generated for the (sub-)document and merged as usual. So the result is the same as merging
+//! `NodeType::from(document)`, without allocating for documents that fit the hypothesis already.
+//!
+//! Serialized documents are observed in a single pass: changes are staged and only applied once the document
+//! turned out valid. Arrays are deserialized as a whole, as their items are merged with each other first.
+
+use crate::embedded::detect_encoded;
+use crate::generate::{HarvestOptions, generate, string_node};
+use crate::merge::merge_into;
+use crate::model::{IntegerNode, NodeType, NumberNode, ObjectNode, ObjectProperty};
+use crate::model::{SchemaHypothesis, StringNode};
+use crate::recursion::{change_folded, try_change_folded};
+use serde::de::value::{MapAccessDeserializer, SeqAccessDeserializer};
+use serde::de::{DeserializeSeed, MapAccess, SeqAccess, Visitor};
+use serde::{Deserialize, Deserializer};
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::ptr;
+
+impl SchemaHypothesis {
+    /// merge a document into the hypothesis, returns whether the hypothesis changed
+    pub fn observe(&mut self, document: &Value) -> bool {
+        self.observe_with(document, &HarvestOptions::default())
+    }
+
+    /// like [`SchemaHypothesis::observe`], with options
+    pub fn observe_with(&mut self, document: &Value, options: &HarvestOptions) -> bool {
+        let Some(root) = &mut self.root else {
+            return self.merge_in_place_with(generate(document, options), options);
+        };
+
+        change_folded(root, options, |root| observe_into(root, document, options))
+    }
+
+    /// like [`SchemaHypothesis::observe`], but for a serialized document, without building a [`Value`]
+    ///
+    /// The hypothesis is left untouched if the document is not valid JSON.
+    pub fn observe_slice(&mut self, document: &[u8]) -> Result<bool, serde_json::Error> {
+        self.observe_slice_with(document, &HarvestOptions::default())
+    }
+
+    /// like [`SchemaHypothesis::observe_slice`], with options
+    pub fn observe_slice_with(
+        &mut self,
+        document: &[u8],
+        options: &HarvestOptions,
+    ) -> Result<bool, serde_json::Error> {
+        let Some(root) = &mut self.root else {
+            let document: Value = serde_json::from_slice(document)?;
+            return Ok(self.observe_with(&document, options));
+        };
+
+        try_change_folded(root, options, |root| {
+            // staged, so invalid documents don't leave a partially widened hypothesis behind
+            let mut deserializer = serde_json::Deserializer::from_slice(document);
+            let patch = Observe(root, options).deserialize(&mut deserializer)?;
+            deserializer.end()?;
+            Ok(match patch {
+                Some(patch) => {
+                    apply(root, patch);
+                    true
+                }
+                None => false,
+            })
+        })
+    }
+}
+
+/// merge `value` into `node_type`, returns whether `node_type` changed
+fn observe_into(node_type: &mut NodeType, value: &Value, options: &HarvestOptions) -> bool {
+    match (&mut *node_type, value) {
+        (NodeType::Object(object), Value::Object(map)) => observe_object(object, map, options),
+        (
+            NodeType::String(StringNode {
+                content: Some(content),
+                ..
+            }),
+            Value::String(s),
+        ) => match detect_encoded(s, options.embedded_json, content.encoding) {
+            Some(document) => observe_into(&mut content.schema, &document, options),
+            None => merge_into(node_type, generate(value, options)),
+        },
+        (NodeType::Any(any), Value::Object(_) | Value::Array(_)) => {
+            let kind: fn(&NodeType) -> bool = if value.is_object() {
+                NodeType::is_object
+            } else {
+                NodeType::is_array
+            };
+            match any
+                .nodes
+                .iter()
+                .find(|n| kind(n))
+                .map(|n| conforms(n, value, options))
+            {
+                None => merge_into(node_type, generate(value, options)),
+                Some(true) => false,
+                Some(false) => {
+                    with_node_in_any(&mut any.nodes, kind, |n| observe_into(n, value, options))
+                }
+            }
+        }
+        (node, value) if conforms(node, value, options) => false,
+        _ => merge_into(node_type, generate(value, options)),
+    }
+}
+
+fn observe_object(
+    object: &mut ObjectNode,
+    map: &Map<String, Value>,
+    options: &HarvestOptions,
+) -> bool {
+    let mut changed = false;
+
+    for (key, property) in &mut object.properties {
+        match map.get(key) {
+            Some(value) => changed |= observe_into(&mut property.node_type, value, options),
+            None if property.required => {
+                property.required = false;
+                changed = true;
+            }
+            None => {}
+        }
+    }
+
+    for (key, value) in map {
+        if !object.properties.contains_key(key) {
+            let property = ObjectProperty::new(generate(value, options)).optional();
+            object.properties.insert(key.clone(), property);
+            changed = true;
+        }
+    }
+
+    changed
+}
+
+/// whether observing the value wouldn't change the node
+fn conforms(node_type: &NodeType, value: &Value, options: &HarvestOptions) -> bool {
+    match (node_type, value) {
+        (NodeType::Null, Value::Null) | (NodeType::Boolean, Value::Bool(_)) => true,
+        (NodeType::Integer(_), Value::Number(n)) => !n.is_f64(),
+        (NodeType::Number(_), Value::Number(n)) => n.is_f64(),
+        // strings without format accept any string
+        (NodeType::String(s), Value::String(v)) => match &s.content {
+            _ if s.is_plain() => true,
+            Some(content) => detect_encoded(v, options.embedded_json, content.encoding)
+                .is_some_and(|document| conforms(&content.schema, &document, options)),
+            None => string_node(v, options) == *s,
+        },
+        (NodeType::Array(array), Value::Array(values)) => match &array.items {
+            None => values.is_empty(),
+            Some(items) => values.iter().all(|value| conforms(items, value, options)),
+        },
+        (NodeType::Object(object), Value::Object(map)) => {
+            map.keys().all(|key| object.properties.contains_key(key))
+                && object
+                    .properties
+                    .iter()
+                    .all(|(key, property)| match map.get(key) {
+                        Some(value) => conforms(&property.node_type, value, options),
+                        None => !property.required,
+                    })
+        }
+        (NodeType::Any(any), Value::Object(_)) => any
+            .nodes
+            .iter()
+            .find(|n| n.is_object())
+            .is_some_and(|n| conforms(n, value, options)),
+        (NodeType::Any(any), Value::Array(_)) => any
+            .nodes
+            .iter()
+            .find(|n| n.is_array())
+            .is_some_and(|n| conforms(n, value, options)),
+        (NodeType::Any(any), value) => any.nodes.contains(&generate(value, options)),
+        _ => false,
+    }
+}
+
+/// apply `f` to the (only) node of the given kind of a union
+fn with_node_in_any<T>(
+    nodes: &mut BTreeSet<NodeType>,
+    kind: fn(&NodeType) -> bool,
+    f: impl FnOnce(&mut NodeType) -> T,
+) -> T {
+    let mut list: Vec<NodeType> = std::mem::take(nodes).into_iter().collect();
+    let result = f(list.iter_mut().find(|n| kind(n)).unwrap());
+    *nodes = list.into_iter().collect();
+    result
+}
+
+/// a change of a node, staged while observing a serialized document
+enum Patch {
+    /// the node merged with the (sub-)document
+    Replace(NodeType),
+    Object(ObjectPatch),
+    /// a change of the object of a union
+    InAny(Box<Patch>),
+}
+
+#[derive(Default)]
+struct ObjectPatch {
+    properties: Vec<(String, Patch)>,
+    /// required properties missing in the document
+    optional: Vec<String>,
+    added: Vec<(String, ObjectProperty)>,
+}
+
+fn apply(node_type: &mut NodeType, patch: Patch) {
+    match (node_type, patch) {
+        (node_type, Patch::Replace(node)) => *node_type = node,
+        (NodeType::Object(object), Patch::Object(patch)) => {
+            for (key, patch) in patch.properties {
+                apply(
+                    &mut object.properties.get_mut(&key).unwrap().node_type,
+                    patch,
+                );
+            }
+            for key in patch.optional {
+                object.properties.get_mut(&key).unwrap().required = false;
+            }
+            object.properties.extend(patch.added);
+        }
+        (NodeType::Any(any), Patch::InAny(patch)) => {
+            with_node_in_any(&mut any.nodes, NodeType::is_object, |node| {
+                apply(node, *patch)
+            });
+        }
+        _ => unreachable!("patch doesn't fit the node"),
+    }
+}
+
+/// merge into a copy of the node, as the node may change only once the document turned out valid
+fn stage(node_type: &NodeType, change: impl FnOnce(&mut NodeType) -> bool) -> Option<Patch> {
+    let mut node_type = node_type.clone();
+    change(&mut node_type).then_some(Patch::Replace(node_type))
+}
+
+fn stage_scalar(node_type: &NodeType, scalar: NodeType) -> Option<Patch> {
+    match node_type {
+        NodeType::Any(any) if any.nodes.contains(&scalar) => None,
+        node_type if *node_type == scalar => None,
+        node_type => stage(node_type, |node_type| merge_into(node_type, scalar)),
+    }
+}
+
+fn stage_value(node_type: &NodeType, value: &Value, options: &HarvestOptions) -> Option<Patch> {
+    if conforms(node_type, value, options) {
+        return None;
+    }
+    stage(node_type, |node_type| {
+        observe_into(node_type, value, options)
+    })
+}
+
+/// [`observe_into`] for a value being deserialized, i.e. without building a [`Value`] for it
+///
+/// Returns the change of the node, if any.
+struct Observe<'a>(&'a NodeType, &'a HarvestOptions);
+
+impl<'de> DeserializeSeed<'de> for Observe<'_> {
+    type Value = Option<Patch>;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Option<Patch>, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de> Visitor<'de> for Observe<'_> {
+    type Value = Option<Patch>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a JSON value")
+    }
+
+    fn visit_unit<E>(self) -> Result<Option<Patch>, E> {
+        Ok(stage_scalar(self.0, NodeType::Null))
+    }
+
+    fn visit_bool<E>(self, _: bool) -> Result<Option<Patch>, E> {
+        Ok(stage_scalar(self.0, NodeType::Boolean))
+    }
+
+    fn visit_i64<E>(self, _: i64) -> Result<Option<Patch>, E> {
+        Ok(stage_scalar(self.0, IntegerNode::new().into()))
+    }
+
+    fn visit_u64<E>(self, _: u64) -> Result<Option<Patch>, E> {
+        Ok(stage_scalar(self.0, IntegerNode::new().into()))
+    }
+
+    fn visit_f64<E>(self, _: f64) -> Result<Option<Patch>, E> {
+        Ok(stage_scalar(self.0, NumberNode::new().into()))
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Option<Patch>, E> {
+        match self.0 {
+            // skip detecting the format if it doesn't matter
+            NodeType::String(s) if s.is_plain() => Ok(None),
+            NodeType::String(s) if s.content.is_some() => {
+                Ok(stage_value(self.0, &Value::String(v.to_string()), self.1))
+            }
+            _ => Ok(stage_scalar(self.0, string_node(v, self.1).into())),
+        }
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, seq: A) -> Result<Option<Patch>, A::Error> {
+        // the items are merged with each other before they are merged into the node
+        let value = Value::deserialize(SeqAccessDeserializer::new(seq))?;
+        Ok(stage_value(self.0, &value, self.1))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<Option<Patch>, A::Error> {
+        match self.0 {
+            NodeType::Object(object) => Ok(observe_map(object, map, self.1)?.map(Patch::Object)),
+            NodeType::Any(any) if any.nodes.iter().any(NodeType::is_object) => {
+                let object = any.nodes.iter().find(|n| n.is_object()).unwrap();
+                let patch = Observe(object, self.1).visit_map(map)?;
+                Ok(patch.map(|patch| Patch::InAny(Box::new(patch))))
+            }
+            node_type => {
+                let value = Value::deserialize(MapAccessDeserializer::new(map))?;
+                Ok(stage_value(node_type, &value, self.1))
+            }
+        }
+    }
+}
+
+fn observe_map<'de, A: MapAccess<'de>>(
+    object: &ObjectNode,
+    mut map: A,
+    options: &HarvestOptions,
+) -> Result<Option<ObjectPatch>, A::Error> {
+    let required = object.properties.values().filter(|p| p.required).count();
+    let mut seen_required: Vec<&ObjectProperty> = vec![];
+    let mut patch = ObjectPatch::default();
+
+    while let Some(key) = map.next_key_seed(Lookup(&object.properties))? {
+        match key {
+            Key::Existing(key, property) => {
+                if property.required && !seen_required.iter().any(|p| ptr::eq(*p, property)) {
+                    seen_required.push(property);
+                }
+                if let Some(property_patch) =
+                    map.next_value_seed(Observe(&property.node_type, options))?
+                {
+                    patch.properties.push((key.clone(), property_patch));
+                }
+            }
+            Key::New(key) => {
+                let value = map.next_value::<Value>()?;
+                let property = ObjectProperty::new(generate(&value, options)).optional();
+                patch.added.push((key, property));
+            }
+        }
+    }
+
+    if seen_required.len() < required {
+        for (key, property) in &object.properties {
+            if property.required && !seen_required.iter().any(|p| ptr::eq(*p, property)) {
+                patch.optional.push(key.clone());
+            }
+        }
+    }
+
+    let unchanged =
+        patch.properties.is_empty() && patch.optional.is_empty() && patch.added.is_empty();
+    Ok((!unchanged).then_some(patch))
+}
+
+enum Key<'a> {
+    Existing(&'a String, &'a ObjectProperty),
+    New(String),
+}
+
+/// look up the property of a key while deserializing it
+struct Lookup<'a>(&'a BTreeMap<String, ObjectProperty>);
+
+impl<'de, 'a> DeserializeSeed<'de> for Lookup<'a> {
+    type Value = Key<'a>;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Key<'a>, D::Error> {
+        deserializer.deserialize_str(self)
+    }
+}
+
+impl<'a> Visitor<'_> for Lookup<'a> {
+    type Value = Key<'a>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a property name")
+    }
+
+    fn visit_str<E>(self, key: &str) -> Result<Key<'a>, E> {
+        Ok(match self.0.get_key_value(key) {
+            Some((key, property)) => Key::Existing(key, property),
+            None => Key::New(key.to_string()),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::SchemaHypothesis;
+    use crate::embedded::EmbeddedJson;
+    use crate::generate::{HarvestOptions, generate};
+    use crate::merge::merge_node_type;
+    use crate::model::NodeType;
+    use serde_json::{Value, json};
+
+    fn documents() -> Vec<Value> {
+        vec![
+            json!({"id": 1, "tags": [], "customer": null}),
+            json!({"id": 2, "tags": ["a"], "customer": {"name": "a"}}),
+            json!({"id": 3, "tags": ["b", 1], "customer": {"name": "b", "since": "2000-01-01"}}),
+            json!({"id": 4, "customer": {"name": "c", "since": "2000-01-01"}}),
+            json!({"id": "5", "tags": [{"a": 1}, [1], {"b": 2}], "customer": {"name": 1}}),
+            json!({"id": 6, "tags": [{"a": 1}, [[]]], "note": "2000-01-01T00:00:00Z"}),
+            json!({"id": 7, "note": "fragile", "children": [{"id": 1, "children": []}]}),
+            json!([1, {"id": 8}]),
+            json!({"id": 9, "tags": ["a"], "customer": {"name": "a"}}),
+            json!({"id": 10, "tags": ["2000-01-01", 2], "history": ["2000-01-01", "a"]}),
+        ]
+    }
+
+    #[test]
+    fn test_observe_same_as_merge() {
+        let mut expected =
+            SchemaHypothesis::new("id".to_string(), "order".to_string(), String::new());
+        let mut observed = expected.clone();
+        let mut observed_slice = expected.clone();
+
+        for document in documents() {
+            let changed = expected.merge_in_place(NodeType::from(&document));
+
+            assert_eq!(observed.observe(&document), changed, "{document}");
+            assert_eq!(observed, expected, "{document}");
+
+            let bytes = serde_json::to_vec(&document).unwrap();
+            assert_eq!(
+                observed_slice.observe_slice(&bytes).unwrap(),
+                changed,
+                "{document}"
+            );
+            assert_eq!(observed_slice, expected, "{document}");
+        }
+    }
+
+    #[test]
+    fn test_observe_embedded_same_as_merge() {
+        let options = HarvestOptions {
+            embedded_json: EmbeddedJson::Base64,
+            ..HarvestOptions::default()
+        };
+        let documents = [
+            json!({"payload": "{\"id\": 1}", "token": "eyJpZCI6IDF9"}),
+            json!({"payload": "{\"id\": 2, \"tags\": []}", "token": "eyJpZCI6IDF9"}),
+            json!({"payload": "{\"id\": \"3\"}", "token": "[1]"}),
+            json!({"payload": "{\"id\": 4}", "token": "W10="}),
+            json!({"payload": "none", "token": "{}"}),
+        ];
+        let mut expected =
+            SchemaHypothesis::new("id".to_string(), "event".to_string(), String::new());
+        let mut observed = expected.clone();
+        let mut observed_slice = expected.clone();
+
+        for document in documents {
+            let changed = expected.merge_in_place(generate(&document, &options));
+
+            assert_eq!(
+                observed.observe_with(&document, &options),
+                changed,
+                "{document}"
+            );
+            assert_eq!(observed, expected, "{document}");
+
+            let bytes = serde_json::to_vec(&document).unwrap();
+            assert_eq!(
+                observed_slice.observe_slice_with(&bytes, &options).unwrap(),
+                changed,
+                "{document}"
+            );
+            assert_eq!(observed_slice, expected, "{document}");
+        }
+    }
+
+    #[test]
+    fn test_observe_unchanged() {
+        let mut hypothesis =
+            SchemaHypothesis::new("id".to_string(), "order".to_string(), String::new());
+        for document in documents() {
+            hypothesis.observe(&document);
+        }
+        let before = hypothesis.clone();
+
+        for document in documents() {
+            assert!(!hypothesis.observe(&document), "{document}");
+            assert!(
+                !hypothesis
+                    .observe_slice(&serde_json::to_vec(&document).unwrap())
+                    .unwrap()
+            );
+        }
+        assert_eq!(hypothesis, before);
+    }
+
+    #[test]
+    fn test_observe_invalid_slice() {
+        let mut hypothesis =
+            SchemaHypothesis::new("id".to_string(), "order".to_string(), String::new());
+        hypothesis.observe(&json!({"id": 1}));
+        let before = hypothesis.clone();
+
+        for document in [&br#"{"id": "a", "note": "#[..], br#"{"id": "a"} {}"#] {
+            let actual = hypothesis.observe_slice(document);
+
+            assert!(actual.is_err());
+            assert_eq!(hypothesis, before);
+        }
+    }
+
+    /// a linked list of `length` nodes
+    fn list(length: usize) -> Value {
+        (0..length).fold(Value::Null, |next, id| json!({"id": id, "next": next}))
+    }
+
+    #[test]
+    fn test_observe_deeper_recursion() {
+        let mut hypothesis =
+            SchemaHypothesis::new("id".to_string(), "list".to_string(), String::new());
+        hypothesis.observe(&list(2));
+        let before = hypothesis.clone();
+
+        for document in [list(3), list(4)] {
+            assert!(!hypothesis.observe(&document), "{document}");
+            let bytes = serde_json::to_vec(&document).unwrap();
+            assert!(!hypothesis.observe_slice(&bytes).unwrap(), "{document}");
+        }
+        assert_eq!(hypothesis, before);
+    }
+
+    #[test]
+    fn test_observe_without_recursion() {
+        let options = HarvestOptions {
+            no_recursion: true,
+            ..HarvestOptions::default()
+        };
+        let mut hypothesis =
+            SchemaHypothesis::new("id".to_string(), "list".to_string(), String::new());
+        hypothesis.observe_with(&list(2), &options);
+
+        assert_eq!(hypothesis.root, Some(NodeType::from(&list(2))));
+        let bytes = serde_json::to_vec(&list(3)).unwrap();
+        assert!(hypothesis.observe_slice_with(&bytes, &options).unwrap());
+        assert_eq!(
+            hypothesis.root,
+            Some(merge_node_type(
+                NodeType::from(&list(2)),
+                NodeType::from(&list(3))
+            ))
+        );
+    }
+}
@@ -0,0 +1,583 @@
+//! parse a JSON schema into a [`SchemaHypothesis`], e.g. to resume harvesting from a published schema
+//!
+//! Supports what [`render_schema`](crate::render_schema) produces plus common hand-written constructs:
+//! `type` arrays, `anyOf`/`oneOf`, `required`, `format`, `contentSchema` (of `application/json`), `items` (also
+//! as tuple) and `$ref` to subschemas of the same document (e.g. `#/$defs/address`). Recursive references become [`RecursiveNode`]s.
+//! Keywords that restrict values further (`minimum`, `pattern`, …) are ignored, the hypothesis knows types only.
+//! Subschemas that don't restrict the type at all (e.g. `{}` or `true`) accept a value of any type.
+
+use crate::SchemaHypothesis;
+use crate::error::ParseError;
+use crate::merge::merge_node_type;
+use crate::model::{
+    ArrayNode, IntegerNode, NodeType, NumberNode, ObjectNode, ObjectProperty, RecursiveNode,
+    StringNode,
+};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+
+/// parse a JSON schema document
+///
+/// # Errors
+///
+/// if the schema (or one of its subschemas) can't be represented as hypothesis
+pub fn parse_schema(schema: &Value) -> Result<SchemaHypothesis, ParseError> {
+    let text = |keyword: &str| {
+        schema
+            .get(keyword)
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string()
+    };
+
+    let mut parser = Parser {
+        document: schema,
+        references: vec![("#".to_string(), 0)],
+    };
+    let root = match parser.parse(schema, "#", 0) {
+        Ok(root) => Some(root),
+        // a rendered hypothesis that has not seen any document yet
+        Err(ParseError::Unconstrained { path }) if path == "#" && has_metadata(schema) => None,
+        Err(e) => return Err(e),
+    };
+
+    Ok(SchemaHypothesis {
+        id: text("$id"),
+        title: text("title"),
+        description: text("description"),
+        root,
+    })
+}
+
+/// whether the schema has keywords besides type-information, i.e. is not just `{}` or `true`
+fn has_metadata(schema: &Value) -> bool {
+    ["$schema", "$id", "title", "description"]
+        .iter()
+        .any(|keyword| schema.get(keyword).is_some())
+}
+
+struct Parser<'a> {
+    document: &'a Value,
+    /// references currently being resolved and the number of objects enclosing them
+    references: Vec<(String, usize)>,
+}
+
+impl Parser<'_> {
+    /// `objects` is the number of objects enclosing the schema
+    fn parse(
+        &mut self,
+        schema: &Value,
+        path: &str,
+        objects: usize,
+    ) -> Result<NodeType, ParseError> {
+        let Value::Object(keywords) = schema else {
+            return match schema {
+                Value::Bool(true) => unconstrained(path),
+                _ => Err(ParseError::InvalidSchema {
+                    path: path.to_string(),
+                }),
+            };
+        };
+
+        // like draft-07, ignore all keywords besides `$ref`
+        if let Some(reference) = keywords.get("$ref") {
+            return self.resolve(reference, path, objects);
+        }
+
+        let mut node_types = vec![];
+        match keywords.get("type") {
+            Some(Value::String(type_name)) => {
+                node_types.push(self.parse_type(keywords, type_name, path, objects)?);
+            }
+            Some(Value::Array(type_names)) => {
+                for type_name in type_names {
+                    let Value::String(type_name) = type_name else {
+                        return Err(unknown_type(path, type_name.to_string()));
+                    };
+                    node_types.push(self.parse_type(keywords, type_name, path, objects)?);
+                }
+            }
+            Some(type_name) => return Err(unknown_type(path, type_name.to_string())),
+            None => node_types.extend(self.infer(keywords, path, objects)?),
+        }
+
+        for keyword in ["anyOf", "oneOf"] {
+            if let Some(schemas) = keywords.get(keyword) {
+                let Value::Array(schemas) = schemas else {
+                    return Err(ParseError::InvalidSchema {
+                        path: format!("{path}/{keyword}"),
+                    });
+                };
+                for (i, schema) in schemas.iter().enumerate() {
+                    node_types.push(self.parse(
+                        schema,
+                        &format!("{path}/{keyword}/{i}"),
+                        objects,
+                    )?);
+                }
+            }
+        }
+
+        match keywords.get("allOf") {
+            None => {}
+            Some(Value::Array(schemas)) if schemas.len() == 1 => {
+                node_types.push(self.parse(&schemas[0], &format!("{path}/allOf/0"), objects)?);
+            }
+            Some(_) => {
+                return Err(ParseError::Unsupported {
+                    path: path.to_string(),
+                    keyword: "allOf".to_string(),
+                });
+            }
+        }
+
+        match node_types.into_iter().reduce(merge_node_type) {
+            Some(node_type) => Ok(node_type),
+            None => unconstrained(path),
+        }
+    }
+
+    fn parse_type(
+        &mut self,
+        keywords: &Map<String, Value>,
+        type_name: &str,
+        path: &str,
+        objects: usize,
+    ) -> Result<NodeType, ParseError> {
+        let node_type = match type_name {
+            "null" => NodeType::Null,
+            "boolean" => NodeType::Boolean,
+            "integer" => IntegerNode::new().into(),
+            "number" => NumberNode::new().into(),
+            "string" => match self.parse_content(keywords, path, objects)? {
+                Some(string) => string.into(),
+                None => {
+                    // formats the hypothesis does not know are dropped
+                    let format = keywords
+                        .get("format")
+                        .and_then(Value::as_str)
+                        .and_then(|format| format.parse().ok());
+                    StringNode::new(format).into()
+                }
+            },
+            "array" => self.parse_array(keywords, path, objects)?.into(),
+            "object" => self.parse_object(keywords, path, objects)?.into(),
+            type_name => return Err(unknown_type(path, format!("\"{type_name}\""))),
+        };
+
+        Ok(node_type)
+    }
+
+    /// a string embedding a JSON document, other media types and unknown encodings are dropped
+    fn parse_content(
+        &mut self,
+        keywords: &Map<String, Value>,
+        path: &str,
+        objects: usize,
+    ) -> Result<Option<StringNode>, ParseError> {
+        let media_type = keywords.get("contentMediaType").and_then(Value::as_str);
+        let (Some("application/json"), Some(schema)) = (media_type, keywords.get("contentSchema"))
+        else {
+            return Ok(None);
+        };
+        let encoding = match keywords.get("contentEncoding").and_then(Value::as_str) {
+            None => None,
+            Some(encoding) => match encoding.parse() {
+                Ok(encoding) => Some(encoding),
+                Err(_) => return Ok(None),
+            },
+        };
+
+        let schema = self.parse(schema, &format!("{path}/contentSchema"), objects)?;
+        Ok(Some(StringNode::embedded(encoding, schema)))
+    }
+
+    fn parse_array(
+        &mut self,
+        keywords: &Map<String, Value>,
+        path: &str,
+        objects: usize,
+    ) -> Result<ArrayNode, ParseError> {
+        let items = match keywords.get("items") {
+            None | Some(Value::Bool(_)) => None,
+            // tuple-validation
+            Some(Value::Array(schemas)) => {
+                let mut items = vec![];
+                for (i, schema) in schemas.iter().enumerate() {
+                    items.push(self.parse(schema, &format!("{path}/items/{i}"), objects)?);
+                }
+                items.into_iter().reduce(merge_node_type)
+            }
+            Some(schema) => Some(self.parse(schema, &format!("{path}/items"), objects)?),
+        };
+
+        Ok(items.map(ArrayNode::new).unwrap_or_default())
+    }
+
+    fn parse_object(
+        &mut self,
+        keywords: &Map<String, Value>,
+        path: &str,
+        objects: usize,
+    ) -> Result<ObjectNode, ParseError> {
+        let required: Vec<&str> = keywords
+            .get("required")
+            .and_then(Value::as_array)
+            .map(|required| required.iter().filter_map(Value::as_str).collect())
+            .unwrap_or_default();
+
+        let mut properties = BTreeMap::new();
+        if let Some(Value::Object(schemas)) = keywords.get("properties") {
+            for (key, schema) in schemas {
+                let path = format!("{path}/properties/{}", escape_pointer(key));
+                let node_type = self.parse(schema, &path, objects + 1)?;
+                properties.insert(
+                    key.clone(),
+                    ObjectProperty {
+                        node_type,
+                        required: required.contains(&key.as_str()),
+                    },
+                );
+            }
+        }
+
+        Ok(ObjectNode::new(properties))
+    }
+
+    /// the type of a schema without `type`
+    fn infer(
+        &mut self,
+        keywords: &Map<String, Value>,
+        path: &str,
+        objects: usize,
+    ) -> Result<Vec<NodeType>, ParseError> {
+        let has = |names: &[&str]| names.iter().any(|name| keywords.contains_key(*name));
+
+        let node_types = if has(&["properties", "required", "additionalProperties"]) {
+            vec![self.parse_object(keywords, path, objects)?.into()]
+        } else if has(&["items"]) {
+            vec![self.parse_array(keywords, path, objects)?.into()]
+        } else if has(&[
+            "format",
+            "pattern",
+            "minLength",
+            "maxLength",
+            "contentSchema",
+        ]) {
+            vec![self.parse_type(keywords, "string", path, objects)?]
+        } else if let Some(value) = keywords.get("const") {
+            vec![NodeType::from(value)]
+        } else if let Some(Value::Array(values)) = keywords.get("enum") {
+            values.iter().map(NodeType::from).collect()
+        } else {
+            vec![]
+        };
+
+        Ok(node_types)
+    }
+
+    fn resolve(
+        &mut self,
+        reference: &Value,
+        path: &str,
+        objects: usize,
+    ) -> Result<NodeType, ParseError> {
+        let unresolvable = || ParseError::UnresolvableRef {
+            path: path.to_string(),
+            reference: reference.to_string(),
+        };
+        let Some(reference) = reference.as_str() else {
+            return Err(unresolvable());
+        };
+
+        if let Some((_, enclosing)) = self.references.iter().find(|(r, _)| r == reference) {
+            // recursion without an object in between (e.g. `a: {"$ref": "#/a"}`) has no meaning
+            return if objects > *enclosing {
+                Ok(RecursiveNode::new(objects - enclosing).into())
+            } else {
+                Err(unresolvable())
+            };
+        }
+
+        let target = reference
+            .strip_prefix('#')
+            .and_then(|pointer| self.document.pointer(pointer))
+            .ok_or_else(unresolvable)?;
+
+        self.references.push((reference.to_string(), objects));
+        let node_type = self.parse(target, reference, objects);
+        self.references.pop();
+
+        node_type
+    }
+}
+
+/// a schema that doesn't restrict the type, only the root can't be represented (it becomes an empty hypothesis)
+fn unconstrained(path: &str) -> Result<NodeType, ParseError> {
+    if path != "#" {
+        return Ok(any_value());
+    }
+    Err(ParseError::Unconstrained {
+        path: path.to_string(),
+    })
+}
+
+/// a value of any type
+fn any_value() -> NodeType {
+    [
+        NodeType::Null,
+        NodeType::Boolean,
+        NumberNode::new().into(),
+        StringNode::default().into(),
+        ArrayNode::default().into(),
+        ObjectNode::new(BTreeMap::new()).into(),
+    ]
+    .into_iter()
+    .reduce(merge_node_type)
+    .unwrap()
+}
+
+fn unknown_type(path: &str, type_name: String) -> ParseError {
+    ParseError::UnknownType {
+        path: path.to_string(),
+        type_name,
+    }
+}
+
+/// escape a key for use in a JSON pointer
+fn escape_pointer(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+#[cfg(test)]
+mod test {
+    use super::parse_schema;
+    use crate::error::ParseError;
+    use crate::model::{
+        AnyNode, ArrayNode, ContentEncoding, IntegerNode, NodeType, NumberNode, ObjectNode,
+        ObjectProperty, RecursiveNode, StringFormat, StringNode,
+    };
+    use crate::{SchemaHypothesis, render_schema};
+    use maplit::{btreemap, btreeset};
+    use serde_json::{Value, json};
+
+    #[test]
+    fn test_round_trip() {
+        let hypothesis = [
+            json!({"id": "f3fa7e18-549f-4ee1-8aeb-1bb8cbf7e956", "at": "2000-01-01T00:00:00Z", "items": [{"sku": "a", "count": 1.5}], "tags": [], "note": null}),
+            json!({"id": 1, "at": "2000-01-01", "items": [{"sku": "b", "count": 2}], "flag": true}),
+            json!({"name": "a", "next": {"name": "b", "next": null}}),
+        ]
+        .iter()
+        .fold(
+            SchemaHypothesis::new("id".to_string(), "order".to_string(), "orders".to_string()),
+            |hypothesis, document| hypothesis.merge(NodeType::from(document)),
+        );
+        let schema: Value = serde_json::from_str(&render_schema(&hypothesis)).unwrap();
+
+        let actual = parse_schema(&schema).unwrap();
+
+        assert_eq!(actual, hypothesis);
+    }
+
+    #[test]
+    fn test_empty() {
+        let hypothesis =
+            SchemaHypothesis::new("id".to_string(), "order".to_string(), String::new());
+        let schema: Value = serde_json::from_str(&render_schema(&hypothesis)).unwrap();
+
+        let actual = parse_schema(&schema).unwrap();
+
+        assert_eq!(actual, hypothesis);
+    }
+
+    #[test]
+    fn test_hand_written() {
+        let schema = json!({
+            "title": "customer",
+            "type": "object",
+            "required": ["id", "address"],
+            "properties": {
+                "id": {"type": ["string", "null"], "format": "uuid"},
+                "address": {"$ref": "#/$defs/address"},
+                "billing": {"oneOf": [{"$ref": "#/$defs/address"}, {"type": "null"}]},
+                "status": {"enum": ["active", "inactive"]},
+                "email": {"type": "string", "format": "email"},
+                "referrer": {"$ref": "#"}
+            },
+            "$defs": {
+                "address": {"properties": {"zip": {"type": "integer", "minimum": 0}}, "required": ["zip"]}
+            }
+        });
+        let address = ObjectNode::new(btreemap! {
+            "zip".to_string() => ObjectProperty::new(IntegerNode::new()),
+        });
+
+        let actual = parse_schema(&schema).unwrap();
+
+        assert_eq!(actual.title, "customer");
+        assert_eq!(
+            actual.root,
+            Some(
+                ObjectNode::new(btreemap! {
+                    "address".to_string() => ObjectProperty::new(address.clone()),
+                    "billing".to_string() => ObjectProperty::new(AnyNode::new(btreeset![NodeType::Null, address.into()])).optional(),
+                    "email".to_string() => ObjectProperty::new(StringNode::default()).optional(),
+                    "id".to_string() => ObjectProperty::new(AnyNode::new(btreeset![NodeType::Null, StringNode::formatted(StringFormat::Uuid).into()])),
+                    "referrer".to_string() => ObjectProperty::new(RecursiveNode::new(1)).optional(),
+                    "status".to_string() => ObjectProperty::new(StringNode::default()).optional(),
+                })
+                .into()
+            )
+        );
+    }
+
+    #[test]
+    fn test_embedded_document() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "payload": {"type": "string", "contentMediaType": "application/json", "contentSchema": {"type": "array", "items": {"type": "integer"}}},
+                "token": {"type": "string", "contentEncoding": "base64", "contentMediaType": "application/json", "contentSchema": {"type": "integer"}},
+                "image": {"type": "string", "contentEncoding": "base64", "contentMediaType": "image/png"}
+            }
+        });
+
+        let actual = parse_schema(&schema).unwrap();
+
+        assert_eq!(
+            actual.root,
+            Some(
+                ObjectNode::new(btreemap! {
+                    "image".to_string() => ObjectProperty::new(StringNode::default()).optional(),
+                    "payload".to_string() => ObjectProperty::new(StringNode::embedded(None, ArrayNode::new(IntegerNode::new().into()).into())).optional(),
+                    "token".to_string() => ObjectProperty::new(StringNode::embedded(Some(ContentEncoding::Base64), IntegerNode::new().into())).optional(),
+                })
+                .into()
+            )
+        );
+    }
+
+    #[test]
+    fn test_recursive_embedded_document() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "id": {"type": "integer"},
+                "parent": {"type": "string", "contentMediaType": "application/json", "contentSchema": {"$ref": "#"}}
+            }
+        });
+        let hypothesis = parse_schema(&schema).unwrap();
+        let rendered: Value = serde_json::from_str(&render_schema(&hypothesis)).unwrap();
+
+        let actual = parse_schema(&rendered).unwrap();
+
+        assert_eq!(
+            rendered["properties"]["parent"]["contentSchema"],
+            json!({"$ref": "#"})
+        );
+        assert_eq!(actual, hypothesis);
+    }
+
+    #[test]
+    fn test_recursive_definition() {
+        let schema = json!({
+            "type": "array",
+            "items": {"$ref": "#/definitions/category"},
+            "definitions": {
+                "category": {
+                    "type": "object",
+                    "properties": {"children": {"type": "array", "items": {"$ref": "#/definitions/category"}}}
+                }
+            }
+        });
+
+        let actual = parse_schema(&schema).unwrap();
+
+        assert_eq!(
+            actual.root,
+            Some(
+                ArrayNode::new(
+                    ObjectNode::new(btreemap! {
+                        "children".to_string() => ObjectProperty::new(ArrayNode::new(RecursiveNode::new(1).into())).optional(),
+                    })
+                    .into()
+                )
+                .into()
+            )
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        let parse = |schema: Value| parse_schema(&schema).unwrap_err();
+
+        assert_eq!(
+            parse(json!({"properties": {"a": {"$ref": "https://example.com/a.json"}}})),
+            ParseError::UnresolvableRef {
+                path: "#/properties/a".to_string(),
+                reference: "\"https://example.com/a.json\"".to_string()
+            }
+        );
+        assert_eq!(
+            parse(json!({"allOf": [{"type": "string"}, {"minLength": 1}]})),
+            ParseError::Unsupported {
+                path: "#".to_string(),
+                keyword: "allOf".to_string()
+            }
+        );
+        assert_eq!(
+            parse(json!({"type": "object", "properties": {"a/b": {"type": "text"}}})),
+            ParseError::UnknownType {
+                path: "#/properties/a~1b".to_string(),
+                type_name: "\"text\"".to_string()
+            }
+        );
+        assert_eq!(
+            parse(json!({})),
+            ParseError::Unconstrained {
+                path: "#".to_string()
+            }
+        );
+        assert_eq!(
+            parse(json!(true)),
+            ParseError::Unconstrained {
+                path: "#".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_unconstrained() {
+        let schema = json!({
+            "type": "object",
+            "required": ["a"],
+            "properties": {"a": {}, "b": true, "c": {"description": "anything"}}
+        });
+        let any_value = || {
+            AnyNode::new(btreeset![
+                NodeType::Null,
+                NodeType::Boolean,
+                NumberNode::new().into(),
+                StringNode::default().into(),
+                ArrayNode::default().into(),
+                ObjectNode::new(btreemap! {}).into(),
+            ])
+        };
+
+        let actual = parse_schema(&schema).unwrap();
+
+        assert_eq!(
+            actual.root,
+            Some(
+                ObjectNode::new(btreemap! {
+                    "a".to_string() => ObjectProperty::new(any_value()),
+                    "b".to_string() => ObjectProperty::new(any_value()).optional(),
+                    "c".to_string() => ObjectProperty::new(any_value()).optional(),
+                })
+                .into()
+            )
+        );
+        assert_eq!(parse_schema(&json!({"title": "order"})).unwrap().root, None);
+    }
+}
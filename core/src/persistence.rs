@@ -0,0 +1,142 @@
+//! save the full hypothesis (unlike the rendered JSON schema, without loss) to resume or merge it later
+//!
+//! The hypothesis is wrapped into an envelope with a version, increased on incompatible changes of the
+//! representation:
+//!
+//! ```json
+//! {"format": "schema-harvester/hypothesis", "version": 1, "hypothesis": {"id": "…", "root": {"type": "object", …}}}
+//! ```
+
+use crate::SchemaHypothesis;
+use crate::error::LoadError;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// marks a document as saved hypothesis
+pub const HYPOTHESIS_FORMAT: &str = "schema-harvester/hypothesis";
+
+/// current version of the representation
+pub const HYPOTHESIS_VERSION: u64 = 1;
+
+#[derive(Serialize)]
+struct Envelope<'a> {
+    format: &'static str,
+    version: u64,
+    hypothesis: &'a SchemaHypothesis,
+}
+
+#[derive(Deserialize)]
+struct Versioned {
+    version: u64,
+    hypothesis: Value,
+}
+
+/// serialize the hypothesis (as pretty JSON), see [`load_hypothesis`] to read it
+#[must_use]
+#[allow(clippy::missing_panics_doc)]
+pub fn save_hypothesis(hypothesis: &SchemaHypothesis) -> String {
+    let envelope = Envelope {
+        format: HYPOTHESIS_FORMAT,
+        version: HYPOTHESIS_VERSION,
+        hypothesis,
+    };
+    serde_json::to_string_pretty(&envelope).unwrap()
+}
+
+/// deserialize a hypothesis saved by [`save_hypothesis`]
+///
+/// # Errors
+///
+/// if the input is not a saved hypothesis or has been saved by an unsupported version
+pub fn load_hypothesis(saved: &str) -> Result<SchemaHypothesis, LoadError> {
+    let versioned: Versioned = serde_json::from_str(saved)?;
+
+    match versioned.version {
+        HYPOTHESIS_VERSION => Ok(serde_json::from_value(versioned.hypothesis)?),
+        version => Err(LoadError::UnsupportedVersion(version)),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{load_hypothesis, save_hypothesis};
+    use crate::SchemaHypothesis;
+    use crate::error::LoadError;
+    use crate::model::NodeType;
+    use serde_json::{Value, json};
+
+    fn hypothesis() -> SchemaHypothesis {
+        [
+            json!({"id": "f3fa7e18-549f-4ee1-8aeb-1bb8cbf7e956", "at": "2000-01-01T00:00:00Z", "items": [{"sku": "a", "count": 1.5}], "tags": [], "note": null}),
+            json!({"id": 1, "at": "2000-01-01", "items": [{"sku": "b", "count": 2}], "flag": true}),
+            json!({"name": "a", "next": {"name": "b", "next": null}}),
+        ]
+        .iter()
+        .fold(
+            SchemaHypothesis::new("id".to_string(), "order".to_string(), "orders".to_string()),
+            |hypothesis, document| hypothesis.merge(NodeType::from(document)),
+        )
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let hypothesis = hypothesis();
+
+        let actual = load_hypothesis(&save_hypothesis(&hypothesis)).unwrap();
+
+        assert_eq!(actual, hypothesis);
+    }
+
+    #[test]
+    fn test_round_trip_empty() {
+        let hypothesis =
+            SchemaHypothesis::new("id".to_string(), "order".to_string(), String::new());
+
+        let actual = load_hypothesis(&save_hypothesis(&hypothesis)).unwrap();
+
+        assert_eq!(actual, hypothesis);
+    }
+
+    #[test]
+    fn test_representation() {
+        let hypothesis =
+            SchemaHypothesis::new("id".to_string(), "order".to_string(), String::new()).merge(
+                NodeType::from(&json!({"at": "2000-01-01", "tags": [1, "a"]})),
+            );
+
+        let actual: Value = serde_json::from_str(&save_hypothesis(&hypothesis)).unwrap();
+
+        assert_eq!(
+            actual,
+            json!({
+                "format": "schema-harvester/hypothesis",
+                "version": 1,
+                "hypothesis": {
+                    "id": "id",
+                    "title": "order",
+                    "description": "",
+                    "root": {
+                        "type": "object",
+                        "properties": {
+                            "at": {"node_type": {"type": "string", "format": "date"}, "required": true},
+                            "tags": {
+                                "node_type": {
+                                    "type": "array",
+                                    "items": {"type": "any", "nodes": [{"type": "integer"}, {"type": "string"}]}
+                                },
+                                "required": true
+                            }
+                        }
+                    }
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn test_unsupported_version() {
+        let actual = load_hypothesis(r#"{"version": 2, "hypothesis": {}}"#);
+
+        assert!(matches!(actual, Err(LoadError::UnsupportedVersion(2))));
+    }
+}
@@ -0,0 +1,372 @@
+//! generate [Pydantic v2](https://docs.pydantic.dev/) models from a [`SchemaHypothesis`]
+
+use crate::SchemaHypothesis;
+use crate::model::{NodeType, ObjectNode, StringFormat};
+use crate::naming::{Named, Names, object_union, snake_case};
+use std::collections::BTreeSet;
+
+const KEYWORDS: &[&str] = &[
+    "False", "None", "True", "and", "as", "assert", "async", "await", "break", "class", "continue",
+    "def", "del", "elif", "else", "except", "finally", "for", "from", "global", "if", "import",
+    "in", "is", "lambda", "nonlocal", "not", "or", "pass", "raise", "return", "try", "while",
+    "with", "yield",
+];
+
+/// names imported by the generated code, the types of `datetime` are aliased so fields may be named after them
+const RESERVED: &[&str] = &[
+    "Any",
+    "BaseModel",
+    "Date",
+    "DateTime",
+    "Field",
+    "Optional",
+    "RootModel",
+    "Time",
+    "UUID",
+    "Union",
+];
+
+/// imports used by the generated code, rendered as `from {module} import {name}` (`name` may be `… as …`)
+type Imports = BTreeSet<(&'static str, &'static str)>;
+
+/// generate a python module with one class per object of the hypothesis.
+///
+/// The root is named after the title; if the root is not an object, it's generated as `RootModel`.
+#[must_use]
+pub fn render_pydantic(schema: &SchemaHypothesis) -> String {
+    let Some(root) = &schema.root else {
+        return String::new();
+    };
+
+    let names = Names::collect_reserving(root, &schema.title, RESERVED);
+    let mut imports = Imports::new();
+    imports.insert(("pydantic", "BaseModel"));
+
+    let mut classes: Vec<String> = names
+        .ordered
+        .iter()
+        .filter_map(|(name, named)| match named {
+            Named::Object(object) if object.properties.is_empty() => None,
+            Named::Object(object) => Some(render_class(name, object, &names, &mut imports)),
+            // unions are rendered inline as `Union[…]`
+            Named::Union(_) => None,
+        })
+        .collect();
+
+    if !root.is_object() {
+        imports.insert(("pydantic", "RootModel"));
+        let root_type = nullable_type(root, &names, &mut imports);
+        classes.push(format!(
+            "class {}(RootModel[{root_type}]):\n    pass",
+            crate::naming::pascal_case(&schema.title)
+        ));
+    }
+
+    let imports = render_imports(&imports);
+
+    format!(
+        "from __future__ import annotations\n\n{imports}\n\n\n{}",
+        classes.join("\n\n\n")
+    )
+}
+
+fn render_imports(imports: &Imports) -> String {
+    let mut lines: Vec<String> = vec![];
+    let mut previous_module = "";
+    for (module, name) in imports {
+        if *module == previous_module {
+            let line = lines.last_mut().expect("module has been added before");
+            line.push_str(", ");
+            line.push_str(name);
+        } else {
+            lines.push(format!("from {module} import {name}"));
+        }
+        previous_module = module;
+    }
+
+    // standard library first, then third-party (i.e. pydantic)
+    let (third_party, std): (Vec<_>, Vec<_>) = lines
+        .into_iter()
+        .partition(|line| line.starts_with("from pydantic "));
+
+    if std.is_empty() {
+        third_party.join("\n")
+    } else {
+        format!("{}\n\n{}", std.join("\n"), third_party.join("\n"))
+    }
+}
+
+fn render_class(name: &str, object: &ObjectNode, names: &Names, imports: &mut Imports) -> String {
+    let mut field_names = BTreeSet::new();
+    let fields: Vec<String> = object
+        .properties
+        .iter()
+        .map(|(key, property)| {
+            let (node_type, nullable) = property.node_type.split_null();
+            let python_type = match node_type {
+                None => {
+                    imports.insert(("typing", "Any"));
+                    "Any".to_string()
+                }
+                Some(node_type) if nullable || !property.required => {
+                    imports.insert(("typing", "Optional"));
+                    format!("Optional[{}]", python_type(node_type, names, imports))
+                }
+                Some(node_type) => python_type(node_type, names, imports),
+            };
+
+            // different keys may result in the same name, e.g. `a-b` and `a_b`
+            let base = field_name(key);
+            let mut field_name = base.clone();
+            let mut suffix = 2;
+            while !field_names.insert(field_name.clone()) {
+                field_name = format!("{base}_{suffix}");
+                suffix += 1;
+            }
+            let mut arguments = vec![];
+            if !property.required {
+                arguments.push("default=None".to_string());
+            }
+            if field_name != *key {
+                arguments.push(format!("alias={}", python_string(key)));
+            }
+
+            match arguments.as_slice() {
+                [] => format!("    {field_name}: {python_type}"),
+                [default] if default == "default=None" => {
+                    format!("    {field_name}: {python_type} = None")
+                }
+                _ => {
+                    imports.insert(("pydantic", "Field"));
+                    format!(
+                        "    {field_name}: {python_type} = Field({})",
+                        arguments.join(", ")
+                    )
+                }
+            }
+        })
+        .collect();
+
+    format!("class {name}(BaseModel):\n{}", fields.join("\n"))
+}
+
+fn python_type(node_type: &NodeType, names: &Names, imports: &mut Imports) -> String {
+    match node_type {
+        NodeType::Boolean => "bool".to_string(),
+        NodeType::Integer(_) => "int".to_string(),
+        NodeType::Number(_) => "float".to_string(),
+        NodeType::Null => "None".to_string(),
+        NodeType::String(s) => {
+            let (module, import, name) = match s.format {
+                None => return "str".to_string(),
+                Some(StringFormat::DateTime) => ("datetime", "datetime as DateTime", "DateTime"),
+                Some(StringFormat::Date) => ("datetime", "date as Date", "Date"),
+                Some(StringFormat::Time) => ("datetime", "time as Time", "Time"),
+                Some(StringFormat::Uuid) => ("uuid", "UUID", "UUID"),
+            };
+            imports.insert((module, import));
+            name.to_string()
+        }
+        NodeType::Array(array) => match &array.items {
+            None => {
+                imports.insert(("typing", "Any"));
+                "list[Any]".to_string()
+            }
+            Some(items) => format!("list[{}]", nullable_type(items, names, imports)),
+        },
+        NodeType::Object(object) if object.properties.is_empty() => {
+            imports.insert(("typing", "Any"));
+            "dict[str, Any]".to_string()
+        }
+        NodeType::Object(object) => names.object(object).to_string(),
+        NodeType::Recursive(recursive) => names.recursive(recursive).to_string(),
+        NodeType::Any(any) => {
+            let members: Vec<String> = match object_union(any) {
+                Some(objects) => objects
+                    .iter()
+                    .map(|object| names.object(object).to_string())
+                    .collect(),
+                None => any
+                    .nodes
+                    .iter()
+                    .filter(|node_type| **node_type != NodeType::Null)
+                    .map(|node_type| python_type(node_type, names, imports))
+                    .collect(),
+            };
+            imports.insert(("typing", "Union"));
+            format!("Union[{}]", members.join(", "))
+        }
+    }
+}
+
+/// the python type of a value that may be null
+fn nullable_type(node_type: &NodeType, names: &Names, imports: &mut Imports) -> String {
+    match node_type.split_null() {
+        (None, _) => "None".to_string(),
+        (Some(node_type), true) => {
+            imports.insert(("typing", "Optional"));
+            format!("Optional[{}]", python_type(node_type, names, imports))
+        }
+        (Some(node_type), false) => python_type(node_type, names, imports),
+    }
+}
+
+/// a name that is a valid python identifier (and not a pydantic private attribute)
+///
+/// Names shadowing an import (which breaks the annotations of the following fields) or in pydantic's `model_`
+/// namespace are changed too.
+fn field_name(key: &str) -> String {
+    let is_identifier = key.chars().next().is_some_and(|c| c.is_ascii_alphabetic())
+        && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+
+    let name = if is_identifier && !KEYWORDS.contains(&key) {
+        key.to_string()
+    } else {
+        snake_case(key)
+    };
+    match name.chars().next() {
+        None => "field".to_string(),
+        Some(c) if c.is_ascii_digit() => format!("field_{name}"),
+        Some(_) if name.starts_with("model_") => format!("field_{name}"),
+        Some(_) if KEYWORDS.contains(&name.as_str()) || RESERVED.contains(&name.as_str()) => {
+            format!("{name}_")
+        }
+        Some(_) => name,
+    }
+}
+
+fn python_string(s: &str) -> String {
+    // JSON string-escaping is a valid python string literal
+    serde_json::Value::String(s.to_string()).to_string()
+}
+
+#[cfg(test)]
+mod test {
+    use super::{field_name, render_pydantic};
+    use crate::SchemaHypothesis;
+    use crate::model::NodeType;
+    use serde_json::{Value, json};
+    use test_case::test_case;
+
+    fn hypothesis(documents: &[Value]) -> SchemaHypothesis {
+        documents.iter().fold(
+            SchemaHypothesis::new("id".to_string(), "order".to_string(), String::new()),
+            |hypothesis, document| hypothesis.merge(NodeType::from(document)),
+        )
+    }
+
+    #[test_case("name", "name")]
+    #[test_case("created-at", "created_at")]
+    #[test_case("@type", "type")]
+    #[test_case("_id", "id")]
+    #[test_case("class", "class_")]
+    #[test_case("1st", "field_1st")]
+    #[test_case("model_config", "field_model_config")]
+    #[test_case("Optional", "Optional_")]
+    #[test_case("date", "date")]
+    fn test_field_name(key: &str, expected: &str) {
+        assert_eq!(field_name(key), expected);
+    }
+
+    #[test]
+    fn test_models() {
+        let hypothesis = hypothesis(&[
+            json!({"id": "f3fa7e18-549f-4ee1-8aeb-1bb8cbf7e956", "created-at": "2000-01-01T00:00:00Z", "items": [{"sku": "a"}], "value": 1, "note": null}),
+            json!({"id": "f3fa7e18-549f-4ee1-8aeb-1bb8cbf7e956", "created-at": "2000-01-01T00:00:00Z", "items": [{"sku": "b"}], "value": "one", "note": "fragile", "class": "A"}),
+        ]);
+
+        let actual = render_pydantic(&hypothesis);
+
+        assert_eq!(
+            actual,
+            r#"from __future__ import annotations
+
+from datetime import datetime as DateTime
+from typing import Optional, Union
+from uuid import UUID
+
+from pydantic import BaseModel, Field
+
+
+class ItemsItem(BaseModel):
+    sku: str
+
+
+class Order(BaseModel):
+    class_: Optional[str] = Field(default=None, alias="class")
+    created_at: DateTime = Field(alias="created-at")
+    id: UUID
+    items: list[ItemsItem]
+    note: Optional[str]
+    value: Union[int, str]"#
+        );
+    }
+
+    #[test]
+    fn test_fields_named_like_types() {
+        let actual = render_pydantic(&hypothesis(&[
+            json!({"date": "2000-01-01", "datetime": "2000-01-01T00:00:00Z", "model_config": 1}),
+        ]));
+
+        assert_eq!(
+            actual,
+            r#"from __future__ import annotations
+
+from datetime import date as Date, datetime as DateTime
+
+from pydantic import BaseModel, Field
+
+
+class Order(BaseModel):
+    date: Date
+    datetime: DateTime
+    field_model_config: int = Field(alias="model_config")"#
+        );
+    }
+
+    #[test]
+    fn test_root_model() {
+        let hypothesis = hypothesis(&[json!([{"id": 1}])]);
+
+        let actual = render_pydantic(&hypothesis);
+
+        assert_eq!(
+            actual,
+            r"from __future__ import annotations
+
+from pydantic import BaseModel, RootModel
+
+
+class OrderItem(BaseModel):
+    id: int
+
+
+class Order(RootModel[list[OrderItem]]):
+    pass"
+        );
+    }
+
+    #[test]
+    fn test_names() {
+        let hypothesis = hypothesis(&[json!({"a-b": 1, "a_b": 2, "field": {"any": true}})]);
+
+        let actual = render_pydantic(&hypothesis);
+
+        assert_eq!(
+            actual,
+            r#"from __future__ import annotations
+
+from pydantic import BaseModel, Field
+
+
+class OrderField(BaseModel):
+    any: bool
+
+
+class Order(BaseModel):
+    a_b: int = Field(alias="a-b")
+    a_b_2: int = Field(alias="a_b")
+    field: OrderField"#
+        );
+    }
+}
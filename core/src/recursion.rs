@@ -0,0 +1,454 @@
+//! detect recursive structures (trees, comment threads, …) and fold them into [`RecursiveNode`]s
+//!
+//! A nested object with the same properties as one of its ancestors, holding values of the same kinds, is taken
+//! as evidence for recursion: it's replaced by a reference to the ancestor and merged into it. Afterward, all
+//! objects occurring at the same position as a reference (i.e. within the same
+//! [`AnyNode`](crate::model::AnyNode)) are merged into the referenced ancestor too, just like objects at the same
+//! position are merged otherwise.
+//!
+//! Without this, every deeper message would produce a new (deeper) hypothesis.
+
+use crate::generate::HarvestOptions;
+use crate::merge::merge_object;
+use crate::model::{AnyNode, NodeType, ObjectNode, RecursiveNode};
+use std::collections::BTreeMap;
+use std::convert::Infallible;
+use std::hash::{DefaultHasher, Hash, Hasher};
+
+#[cfg(test)]
+fn fold_recursion(mut node_type: NodeType) -> NodeType {
+    fold_recursion_in_place(&mut node_type);
+    node_type
+}
+
+/// returns whether any recursion was folded
+fn fold_recursion_in_place(node_type: &mut NodeType) -> bool {
+    let mut folded = false;
+    // each step reduces the number of objects, so this terminates
+    while fold_step(node_type) {
+        folded = true;
+    }
+    folded
+}
+
+/// fold recursion unless disabled by the options, returns whether any recursion was folded
+pub(crate) fn fold_recursion_with(node_type: &mut NodeType, options: &HarvestOptions) -> bool {
+    !options.no_recursion && fold_recursion_in_place(node_type)
+}
+
+/// apply `change` to a folded node and fold it again, returns whether the node changed after all
+///
+/// Folding may undo a change, e.g. when a deeper level of a known recursion is merged back into its ancestor.
+pub(crate) fn change_folded(
+    node_type: &mut NodeType,
+    options: &HarvestOptions,
+    change: impl FnOnce(&mut NodeType) -> bool,
+) -> bool {
+    try_change_folded(node_type, options, |node_type| {
+        Ok::<_, Infallible>(change(node_type))
+    })
+    .unwrap_or_else(|never| match never {})
+}
+
+/// like [`change_folded`], for changes that may fail
+pub(crate) fn try_change_folded<E>(
+    node_type: &mut NodeType,
+    options: &HarvestOptions,
+    change: impl FnOnce(&mut NodeType) -> Result<bool, E>,
+) -> Result<bool, E> {
+    if options.no_recursion {
+        return change(node_type);
+    }
+    // without references, folding can't restore the previous node, as the result contains a reference then
+    let previous = contains_recursion(node_type).then(|| fingerprint(node_type));
+    if !change(node_type)? {
+        return Ok(false);
+    }
+    if !fold_recursion_in_place(node_type) {
+        return Ok(true);
+    }
+    Ok(previous.is_none_or(|previous| previous != fingerprint(node_type)))
+}
+
+/// a hash of the node, to compare with a previous version without keeping a copy (collisions are negligible)
+fn fingerprint(node_type: &NodeType) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    node_type.hash(&mut hasher);
+    hasher.finish()
+}
+
+fn contains_recursion(node_type: &NodeType) -> bool {
+    match node_type {
+        NodeType::Recursive(_) => true,
+        NodeType::Object(object) => object
+            .properties
+            .values()
+            .any(|property| contains_recursion(&property.node_type)),
+        NodeType::Array(array) => array.items.as_deref().is_some_and(contains_recursion),
+        NodeType::Any(any) => any.nodes.iter().any(contains_recursion),
+        NodeType::String(string) => string
+            .content
+            .as_ref()
+            .is_some_and(|content| contains_recursion(&content.schema)),
+        _ => false,
+    }
+}
+
+/// fold the first recursion found, returns whether anything changed
+fn fold_step(node_type: &mut NodeType) -> bool {
+    match node_type {
+        NodeType::Object(object) => {
+            if let Some(descendant) = take_recursive(object) {
+                let ancestor = std::mem::replace(object, ObjectNode::new(Default::default()));
+                *object = merge_object(ancestor, descendant);
+                return true;
+            }
+            object
+                .properties
+                .values_mut()
+                .any(|property| fold_step(&mut property.node_type))
+        }
+        NodeType::Array(array) => array.items.as_deref_mut().is_some_and(fold_step),
+        NodeType::Any(any) => modify_any(any, fold_step),
+        NodeType::String(string) => string
+            .content
+            .as_mut()
+            .is_some_and(|content| fold_step(&mut content.schema)),
+        _ => false,
+    }
+}
+
+/// the kinds of non-null values of a node, as bit set
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Kinds(u8);
+
+impl Kinds {
+    const ARRAY: u8 = 1;
+    const BOOLEAN: u8 = 1 << 1;
+    const NUMBER: u8 = 1 << 2;
+    /// objects and references to objects
+    const OBJECT: u8 = 1 << 3;
+    const STRING: u8 = 1 << 4;
+
+    fn of(node_type: &NodeType) -> Self {
+        Kinds(match node_type {
+            NodeType::Any(any) => any.nodes.iter().fold(0, |kinds, n| kinds | Kinds::of(n).0),
+            NodeType::Array(_) => Self::ARRAY,
+            NodeType::Boolean => Self::BOOLEAN,
+            NodeType::Integer(_) | NodeType::Number(_) => Self::NUMBER,
+            NodeType::Null => 0,
+            NodeType::Object(_) | NodeType::Recursive(_) => Self::OBJECT,
+            NodeType::String(_) => Self::STRING,
+        })
+    }
+
+    /// values that are `null` only are compatible with any kind, e.g. the end of a linked list
+    fn compatible(self, other: Kinds) -> bool {
+        self.0 == 0 || other.0 == 0 || self.0 & other.0 != 0
+    }
+}
+
+/// the kinds of values of each property, to compare objects structurally
+type Shape = BTreeMap<String, Kinds>;
+
+/// find a descendant to fold into the given object, replace it by a reference and return it
+fn take_recursive(object: &mut ObjectNode) -> Option<ObjectNode> {
+    // search without allocating first, as most objects are not recursive
+    let found = object
+        .properties
+        .values()
+        .any(|property| find(&property.node_type, object, 1));
+    if !found {
+        return None;
+    }
+
+    let shape: Shape = shape(object)
+        .map(|(key, kinds)| (key.clone(), kinds))
+        .collect();
+    object.properties.values_mut().find_map(|property| {
+        take(
+            &mut property.node_type,
+            shape.iter().map(|(k, v)| (k, *v)),
+            1,
+        )
+    })
+}
+
+fn shape(object: &ObjectNode) -> impl ExactSizeIterator<Item = (&String, Kinds)> + Clone {
+    object
+        .properties
+        .iter()
+        .map(|(key, property)| (key, Kinds::of(&property.node_type)))
+}
+
+/// whether an object has the same properties as an ancestor, with values of compatible kinds
+///
+/// Objects with a single property are envelopes (e.g. `{"data": {"data": …}}`) rather than recursion.
+fn same_shape<'a>(
+    object: &ObjectNode,
+    ancestor: impl ExactSizeIterator<Item = (&'a String, Kinds)>,
+) -> bool {
+    object.properties.len() > 1
+        && object.properties.len() == ancestor.len()
+        && shape(object)
+            .zip(ancestor)
+            .all(|((key, kinds), (ancestor_key, ancestor_kinds))| {
+                key == ancestor_key && kinds.compatible(ancestor_kinds)
+            })
+}
+
+/// whether the node contains a descendant to fold into `ancestor`, see [`take`]
+fn find(node_type: &NodeType, ancestor: &ObjectNode, depth: usize) -> bool {
+    match node_type {
+        NodeType::Object(object) if same_shape(object, shape(ancestor)) => true,
+        NodeType::Object(object) => object
+            .properties
+            .values()
+            .any(|property| find(&property.node_type, ancestor, depth + 1)),
+        NodeType::Array(array) => array
+            .items
+            .as_deref()
+            .is_some_and(|items| find(items, ancestor, depth)),
+        NodeType::Any(any) if references_with_object(any, depth) => true,
+        NodeType::Any(any) => any.nodes.iter().any(|n| find(n, ancestor, depth)),
+        NodeType::String(string) => string
+            .content
+            .as_ref()
+            .is_some_and(|content| find(&content.schema, ancestor, depth)),
+        _ => false,
+    }
+}
+
+/// whether the union holds a reference to the object `depth` levels up and an object to merge into it
+fn references_with_object(any: &AnyNode, depth: usize) -> bool {
+    any.nodes.contains(&RecursiveNode::new(depth).into())
+        && any.nodes.iter().any(NodeType::is_object)
+}
+
+fn take<'a>(
+    node_type: &mut NodeType,
+    ancestor: impl ExactSizeIterator<Item = (&'a String, Kinds)> + Clone,
+    depth: usize,
+) -> Option<ObjectNode> {
+    match node_type {
+        NodeType::Object(object) if same_shape(object, ancestor.clone()) => {
+            let NodeType::Object(object) =
+                std::mem::replace(node_type, RecursiveNode::new(depth).into())
+            else {
+                unreachable!()
+            };
+            Some(rebase(object, depth))
+        }
+        NodeType::Object(object) => object
+            .properties
+            .values_mut()
+            .find_map(|property| take(&mut property.node_type, ancestor.clone(), depth + 1)),
+        NodeType::Array(array) => array
+            .items
+            .as_deref_mut()
+            .and_then(|items| take(items, ancestor, depth)),
+        NodeType::Any(any) if references_with_object(any, depth) => {
+            let object = any.nodes.iter().find(|n| n.is_object()).cloned().unwrap();
+            any.nodes.remove(&object);
+            if any.nodes.len() == 1 {
+                *node_type = any.nodes.pop_first().unwrap();
+            }
+            let NodeType::Object(object) = object else {
+                unreachable!()
+            };
+            Some(rebase(object, depth))
+        }
+        NodeType::Any(any) => {
+            let mut taken = None;
+            modify_any(any, |node_type| {
+                taken = take(node_type, ancestor.clone(), depth);
+                taken.is_some()
+            });
+            taken
+        }
+        NodeType::String(string) => string
+            .content
+            .as_mut()
+            .and_then(|content| take(&mut content.schema, ancestor, depth)),
+        _ => None,
+    }
+}
+
+/// apply `f` to the nodes of the union until it returns `true`
+fn modify_any(any: &mut AnyNode, mut f: impl FnMut(&mut NodeType) -> bool) -> bool {
+    let mut nodes: Vec<NodeType> = std::mem::take(&mut any.nodes).into_iter().collect();
+    let modified = nodes.iter_mut().any(&mut f);
+    any.nodes = nodes.into_iter().collect();
+    modified
+}
+
+/// adjust the references within an object that moves up `distance` levels to the ancestor it is merged into
+fn rebase(object: ObjectNode, distance: usize) -> ObjectNode {
+    let NodeType::Object(object) = rebase_node(object.into(), distance, 1) else {
+        unreachable!()
+    };
+    object
+}
+
+/// `level` is the number of objects (of the moved object) enclosing the given node
+fn rebase_node(node_type: NodeType, distance: usize, level: usize) -> NodeType {
+    match node_type {
+        NodeType::Recursive(RecursiveNode { depth }) if depth > level => {
+            // references to objects between the ancestor and the moved object now reference the ancestor
+            let depth = if depth - level <= distance {
+                level
+            } else {
+                depth - distance
+            };
+            RecursiveNode::new(depth).into()
+        }
+        NodeType::Object(mut object) => {
+            for property in object.properties.values_mut() {
+                let node_type = std::mem::replace(&mut property.node_type, NodeType::Null);
+                property.node_type = rebase_node(node_type, distance, level + 1);
+            }
+            NodeType::Object(object)
+        }
+        NodeType::Array(mut array) => {
+            array.items = array
+                .items
+                .map(|items| Box::new(rebase_node(*items, distance, level)));
+            NodeType::Array(array)
+        }
+        NodeType::Any(any) => AnyNode::new(
+            any.nodes
+                .into_iter()
+                .map(|node_type| rebase_node(node_type, distance, level))
+                .collect(),
+        )
+        .into(),
+        NodeType::String(mut string) => {
+            if let Some(content) = &mut string.content {
+                let schema = std::mem::replace(&mut *content.schema, NodeType::Null);
+                *content.schema = rebase_node(schema, distance, level);
+            }
+            NodeType::String(string)
+        }
+        node_type => node_type,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::fold_recursion;
+    use crate::model::{
+        AnyNode, ArrayNode, IntegerNode, NodeType, ObjectNode, ObjectProperty, RecursiveNode,
+        StringNode,
+    };
+    use maplit::{btreemap, btreeset};
+    use serde_json::json;
+
+    #[test]
+    fn test_tree() {
+        let node_type = NodeType::from(&json!({
+            "name": "a",
+            "children": [{"name": "b", "children": [{"name": "c"}]}, {"name": "d"}]
+        }));
+
+        let actual = fold_recursion(node_type);
+
+        assert_eq!(
+            actual,
+            ObjectNode::new(btreemap! {
+                "name".to_string() => ObjectProperty::new(StringNode::default()),
+                "children".to_string() => ObjectProperty::new(ArrayNode::new(RecursiveNode::new(1).into())).optional(),
+            })
+            .into()
+        );
+    }
+
+    #[test]
+    fn test_linked_list() {
+        let node_type =
+            NodeType::from(&json!({"id": 1, "next": {"id": 2, "next": {"id": 3, "next": null}}}));
+
+        let actual = fold_recursion(node_type);
+
+        assert_eq!(
+            actual,
+            ObjectNode::new(btreemap! {
+                "id".to_string() => ObjectProperty::new(IntegerNode::new()),
+                "next".to_string() => ObjectProperty::new(AnyNode::new(btreeset![NodeType::Null, RecursiveNode::new(1).into()])),
+            })
+            .into()
+        );
+    }
+
+    #[test]
+    fn test_nested_recursion() {
+        let node_type = NodeType::from(&json!({
+            "title": "post",
+            "comments": [{"text": "a", "replies": [{"text": "b", "replies": []}]}]
+        }));
+
+        let actual = fold_recursion(node_type);
+
+        assert_eq!(
+            actual,
+            ObjectNode::new(btreemap! {
+                "title".to_string() => ObjectProperty::new(StringNode::default()),
+                "comments".to_string() => ObjectProperty::new(ArrayNode::new(ObjectNode::new(btreemap! {
+                    "text".to_string() => ObjectProperty::new(StringNode::default()),
+                    "replies".to_string() => ObjectProperty::new(ArrayNode::new(RecursiveNode::new(1).into())),
+                }).into())),
+            })
+            .into()
+        );
+    }
+
+    #[test]
+    fn test_similar_but_not_recursive() {
+        let node_type = NodeType::from(&json!({"id": 1, "user": {"id": 2}}));
+
+        let actual = fold_recursion(node_type.clone());
+
+        assert_eq!(actual, node_type);
+    }
+
+    #[test]
+    fn test_envelope_not_recursive() {
+        let node_type = NodeType::from(&json!({"data": {"data": {"id": 1, "name": "a"}}}));
+
+        let actual = fold_recursion(node_type.clone());
+
+        assert_eq!(actual, node_type);
+    }
+
+    #[test]
+    fn test_same_keys_but_different_types() {
+        let node_type = NodeType::from(&json!({"data": {"data": 5}}));
+
+        let actual = fold_recursion(node_type.clone());
+
+        assert_eq!(actual, node_type);
+    }
+
+    #[test]
+    fn test_embedded_document() {
+        let node_type = NodeType::from(&json!({"id": 1, "next": {"id": 2, "next": null}}));
+        let string = |node_type| NodeType::from(StringNode::embedded(None, node_type));
+
+        let actual = fold_recursion(
+            ObjectNode::new(btreemap! {
+                "payload".to_string() => ObjectProperty::new(string(node_type)),
+            })
+            .into(),
+        );
+
+        assert_eq!(
+            actual,
+            ObjectNode::new(btreemap! {
+                "payload".to_string() => ObjectProperty::new(string(ObjectNode::new(btreemap! {
+                    "id".to_string() => ObjectProperty::new(IntegerNode::new()),
+                    "next".to_string() => ObjectProperty::new(AnyNode::new(btreeset![NodeType::Null, RecursiveNode::new(1).into()])),
+                }).into())),
+            })
+            .into()
+        );
+    }
+}
@@ -0,0 +1,326 @@
+//! hoist objects into `definitions`, replacing the occurrences by `$ref`
+//!
+//! Objects referenced by a [`RecursiveNode`](crate::model::RecursiveNode) are always hoisted, objects that
+//! occur more than once optionally.
+
+use crate::SchemaHypothesis;
+use crate::model::{NodeType, ObjectNode};
+use crate::naming::Names;
+use crate::renderer::Render;
+use serde_json::{Map, Value, json};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// key of the placeholder rendered for a recursive reference, replaced by `$ref` once the referenced object is known
+pub(super) const RECURSIVE: &str = "$recursive";
+
+pub(super) fn render_with_definitions(schema: &SchemaHypothesis, extract_repeated: bool) -> Value {
+    let mut rendered = schema.render();
+    let Some(root) = &schema.root else {
+        return rendered;
+    };
+
+    let mut occurrences = BTreeMap::new();
+    let mut referenced = BTreeSet::new();
+    collect_objects(root, &mut vec![], &mut occurrences, &mut referenced);
+
+    // the root is referenced as `#`
+    let root_object = match root {
+        NodeType::Object(object) => Some(object),
+        _ => None,
+    };
+    let names = Names::collect(root, &schema.title);
+    let definitions = Definitions(
+        occurrences
+            .into_iter()
+            .filter(|(object, count)| {
+                let repeated = extract_repeated && *count > 1 && !object.properties.is_empty();
+                Some(*object) != root_object && (repeated || referenced.contains(object))
+            })
+            .map(|(object, _)| (names.object(object).to_string(), object.render()))
+            .collect(),
+    );
+
+    definitions.replace_nested(&mut rendered, Some("#".to_string()), &mut vec![]);
+
+    if definitions.0.is_empty() {
+        return rendered;
+    }
+
+    let rendered_definitions: Map<String, Value> = definitions
+        .0
+        .iter()
+        .map(|(name, definition)| {
+            let mut definition = definition.clone();
+            let reference = format!("#/definitions/{name}");
+            definitions.replace_nested(&mut definition, Some(reference), &mut vec![]);
+            (name.clone(), definition)
+        })
+        .collect();
+    rendered["definitions"] = Value::Object(rendered_definitions);
+
+    rendered
+}
+
+/// whether the node contains a recursive reference
+pub(super) fn is_recursive(node_type: &NodeType) -> bool {
+    match node_type {
+        NodeType::Recursive(_) => true,
+        NodeType::Object(object) => object
+            .properties
+            .values()
+            .any(|property| is_recursive(&property.node_type)),
+        NodeType::Array(array) => array.items.as_deref().is_some_and(is_recursive),
+        NodeType::Any(any) => any.nodes.iter().any(is_recursive),
+        NodeType::String(string) => string
+            .content
+            .as_ref()
+            .is_some_and(|content| is_recursive(&content.schema)),
+        _ => false,
+    }
+}
+
+/// count how often each object occurs (without descending into an object seen before) and collect the
+/// objects referenced recursively
+fn collect_objects<'a>(
+    node_type: &'a NodeType,
+    enclosing: &mut Vec<&'a ObjectNode>,
+    occurrences: &mut BTreeMap<&'a ObjectNode, usize>,
+    referenced: &mut BTreeSet<&'a ObjectNode>,
+) {
+    match node_type {
+        NodeType::Object(object) => {
+            let count = occurrences.entry(object).or_default();
+            *count += 1;
+            if *count == 1 {
+                enclosing.push(object);
+                for property in object.properties.values() {
+                    collect_objects(&property.node_type, enclosing, occurrences, referenced);
+                }
+                enclosing.pop();
+            }
+        }
+        NodeType::Array(array) => {
+            if let Some(items) = &array.items {
+                collect_objects(items, enclosing, occurrences, referenced);
+            }
+        }
+        NodeType::Any(any) => {
+            for node_type in &any.nodes {
+                collect_objects(node_type, enclosing, occurrences, referenced);
+            }
+        }
+        NodeType::Recursive(recursive) => {
+            if let Some(index) = enclosing.len().checked_sub(recursive.depth) {
+                referenced.insert(enclosing[index]);
+            }
+        }
+        NodeType::String(string) => {
+            if let Some(content) = &string.content {
+                collect_objects(&content.schema, enclosing, occurrences, referenced);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// names and rendered schemas of the hoisted objects
+struct Definitions(Vec<(String, Value)>);
+
+impl Definitions {
+    /// `enclosing` are the references of the objects enclosing the schema (if hoisted)
+    fn replace(&self, schema: &mut Value, enclosing: &mut Vec<Option<String>>) {
+        if let Some(depth) = schema.get(RECURSIVE).and_then(Value::as_u64) {
+            let reference = usize::try_from(depth)
+                .ok()
+                .and_then(|depth| enclosing.len().checked_sub(depth))
+                .and_then(|index| enclosing[index].clone())
+                .unwrap_or_else(|| "#".to_string());
+            *schema = json!({"$ref": reference});
+            return;
+        }
+
+        match self.0.iter().find(|(_, definition)| definition == schema) {
+            Some((name, _)) => *schema = json!({"$ref": format!("#/definitions/{name}")}),
+            None => self.replace_nested(schema, None, enclosing),
+        }
+    }
+
+    /// replace the subschemas of the given schema, `reference` is the reference to the schema itself
+    fn replace_nested(
+        &self,
+        schema: &mut Value,
+        reference: Option<String>,
+        enclosing: &mut Vec<Option<String>>,
+    ) {
+        let is_object = schema.get("type") == Some(&json!("object"));
+        if is_object {
+            enclosing.push(reference);
+        }
+
+        if let Some(properties) = schema.get_mut("properties").and_then(Value::as_object_mut) {
+            for property in properties.values_mut() {
+                self.replace(property, enclosing);
+            }
+        }
+        if let Some(items) = schema.get_mut("items") {
+            self.replace(items, enclosing);
+        }
+        if let Some(any_of) = schema.get_mut("anyOf").and_then(Value::as_array_mut) {
+            for schema in any_of {
+                self.replace(schema, enclosing);
+            }
+        }
+        if let Some(content_schema) = schema.get_mut("contentSchema") {
+            self.replace(content_schema, enclosing);
+        }
+
+        if is_object {
+            enclosing.pop();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::render_with_definitions;
+    use crate::SchemaHypothesis;
+    use crate::model::{
+        IntegerNode, NodeType, ObjectNode, ObjectProperty, RecursiveNode, StringNode,
+    };
+    use maplit::btreemap;
+    use serde_json::{Value, json};
+
+    fn hypothesis(document: &Value) -> SchemaHypothesis {
+        SchemaHypothesis::new("id".to_string(), "order".to_string(), String::new())
+            .merge(NodeType::from(document))
+    }
+
+    #[test]
+    fn test_repeated_objects() {
+        let hypothesis = hypothesis(&json!({
+            "billing": {"address": {"street": "Main", "city": {"name": "A"}}},
+            "shipping": {"address": {"street": "Main", "city": {"name": "A"}}, "express": true},
+            "pickup": [{"city": {"name": "A"}}]
+        }));
+
+        let actual = render_with_definitions(&hypothesis, true);
+
+        assert_eq!(
+            actual["properties"],
+            json!({
+                "billing": {
+                    "type": "object",
+                    "required": ["address"],
+                    "properties": {"address": {"$ref": "#/definitions/Address"}}
+                },
+                "pickup": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "required": ["city"],
+                        "properties": {"city": {"$ref": "#/definitions/City"}}
+                    }
+                },
+                "shipping": {
+                    "type": "object",
+                    "required": ["address", "express"],
+                    "properties": {
+                        "address": {"$ref": "#/definitions/Address"},
+                        "express": {"type": "boolean"}
+                    }
+                }
+            })
+        );
+        assert_eq!(
+            actual["definitions"],
+            json!({
+                "Address": {
+                    "type": "object",
+                    "required": ["city", "street"],
+                    "properties": {
+                        "city": {"$ref": "#/definitions/City"},
+                        "street": {"type": "string"}
+                    }
+                },
+                "City": {
+                    "type": "object",
+                    "required": ["name"],
+                    "properties": {"name": {"type": "string"}}
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn test_unique_objects() {
+        let hypothesis = hypothesis(
+            &json!({"billing": {"street": "Main"}, "shipping": {"street": "Main", "zip": 1}}),
+        );
+
+        let actual = render_with_definitions(&hypothesis, true);
+
+        assert_eq!(actual.get("definitions"), None);
+        assert_eq!(
+            actual["properties"]["billing"]["properties"],
+            json!({"street": {"type": "string"}})
+        );
+    }
+
+    #[test]
+    fn test_recursion() {
+        let hypothesis = hypothesis(&json!({
+            "name": "root",
+            "categories": [{"name": "a", "children": [{"name": "b", "children": []}]}]
+        }));
+
+        let actual = render_with_definitions(&hypothesis, false);
+
+        assert_eq!(
+            actual["properties"]["categories"]["items"],
+            json!({"$ref": "#/definitions/CategoriesItem"})
+        );
+        assert_eq!(
+            actual["definitions"],
+            json!({
+                "CategoriesItem": {
+                    "type": "object",
+                    "required": ["children", "name"],
+                    "properties": {
+                        "children": {"type": "array", "items": {"$ref": "#/definitions/CategoriesItem"}},
+                        "name": {"type": "string"}
+                    }
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn test_recursive_root() {
+        let hypothesis = hypothesis(&json!({"id": 1, "next": {"id": 2, "next": null}}));
+
+        let actual = render_with_definitions(&hypothesis, false);
+
+        assert_eq!(actual.get("definitions"), None);
+        assert_eq!(
+            actual["properties"]["next"],
+            json!({"anyOf": [{"type": "null"}, {"$ref": "#"}]})
+        );
+    }
+
+    #[test]
+    fn test_recursive_embedded_document() {
+        let root = ObjectNode::new(btreemap! {
+            "id".to_string() => ObjectProperty::new(IntegerNode::new()),
+            "parent".to_string() => ObjectProperty::new(StringNode::embedded(None, RecursiveNode::new(1).into())),
+        });
+        let hypothesis = SchemaHypothesis::new("id".to_string(), "node".to_string(), String::new())
+            .merge(root.into());
+
+        let actual = render_with_definitions(&hypothesis, false);
+
+        assert_eq!(
+            actual["properties"]["parent"],
+            json!({"type": "string", "contentMediaType": "application/json", "contentSchema": {"$ref": "#"}})
+        );
+    }
+}
@@ -3,19 +3,39 @@ use serde_json::Value;
 
 mod any;
 mod array;
+mod definitions;
 mod node;
 mod object;
 mod schema;
 mod string;
 
+#[derive(Clone, Debug, Default)]
+pub struct JsonSchemaOptions {
+    /// hoist objects that occur more than once into `definitions` and reference them via `$ref`
+    pub extract_definitions: bool,
+}
+
 #[must_use]
-#[allow(clippy::missing_panics_doc)]
 pub fn render_schema(schema: &SchemaHypothesis) -> String {
-    serde_json::to_string_pretty(&render_json_schema(schema)).unwrap()
+    render_schema_with_options(schema, &JsonSchemaOptions::default())
+}
+
+#[must_use]
+#[allow(clippy::missing_panics_doc)]
+pub fn render_schema_with_options(
+    schema: &SchemaHypothesis,
+    options: &JsonSchemaOptions,
+) -> String {
+    serde_json::to_string_pretty(&render_json_schema(schema, options)).unwrap()
 }
 
-fn render_json_schema(schema: &SchemaHypothesis) -> Value {
-    schema.render()
+fn render_json_schema(schema: &SchemaHypothesis, options: &JsonSchemaOptions) -> Value {
+    let recursive = schema.root.as_ref().is_some_and(definitions::is_recursive);
+    if options.extract_definitions || recursive {
+        definitions::render_with_definitions(schema, options.extract_definitions)
+    } else {
+        schema.render()
+    }
 }
 
 trait Render {
@@ -1,5 +1,6 @@
 use crate::model::NodeType;
 use crate::renderer::Render;
+use crate::renderer::definitions::RECURSIVE;
 use serde_json::{Value, json};
 
 impl Render for NodeType {
@@ -13,6 +14,8 @@ impl Render for NodeType {
             NodeType::Array(a) => a.render(),
             NodeType::Object(o) => o.render(),
             NodeType::Any(a) => a.render(),
+            // placeholder, see [`definitions`](crate::renderer::definitions)
+            NodeType::Recursive(r) => json!({RECURSIVE: r.depth}),
         }
     }
 }
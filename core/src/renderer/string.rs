@@ -1,21 +1,25 @@
 use crate::model::StringNode;
 use crate::renderer::Render;
-use serde_json::json;
+use serde_json::{Value, json};
 
 impl Render for &StringNode {
-    fn render(&self) -> serde_json::Value {
-        match &self.format {
-            None => json!({
-                "type": "string",
-            }),
-            Some(format) => {
-                let format: &str = format.into();
-                json!({
-                    "type": "string",
-                    "format": format
-                })
+    fn render(&self) -> Value {
+        let mut schema = json!({
+            "type": "string",
+        });
+        if let Some(format) = &self.format {
+            let format: &str = format.into();
+            schema["format"] = format.into();
+        }
+        if let Some(content) = &self.content {
+            if let Some(encoding) = content.encoding {
+                let encoding: &str = encoding.into();
+                schema["contentEncoding"] = encoding.into();
             }
+            schema["contentMediaType"] = "application/json".into();
+            schema["contentSchema"] = content.schema.render();
         }
+        schema
     }
 }
 
@@ -23,7 +27,7 @@ impl Render for &StringNode {
 mod test {
     use serde_json::json;
 
-    use crate::model::{NodeType, StringFormat, StringNode};
+    use crate::model::{ContentEncoding, IntegerNode, NodeType, StringFormat, StringNode};
     use crate::renderer::Render;
 
     #[test]
@@ -43,4 +47,22 @@ mod test {
 
         assert_eq!(actual, json!({ "type": "string", "format": "date-time" }));
     }
+
+    #[test]
+    fn render_string_with_embedded_document() {
+        let node: NodeType =
+            StringNode::embedded(Some(ContentEncoding::Base64), IntegerNode::new().into()).into();
+
+        let actual = node.render();
+
+        assert_eq!(
+            actual,
+            json!({
+                "type": "string",
+                "contentEncoding": "base64",
+                "contentMediaType": "application/json",
+                "contentSchema": { "type": "integer" }
+            })
+        );
+    }
 }
@@ -0,0 +1,336 @@
+//! harvest a sample of huge inputs rather than every document
+//!
+//! Documents are sampled at random with a given rate, or uniformly into a reservoir of fixed size (see
+//! [`Reservoir`]). Harvesting stops after a number of documents, some time, or once the hypothesis didn't
+//! change for a number of consecutive documents.
+
+use crate::error::SamplingError;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Deserializer};
+use std::fmt::{Display, Formatter};
+use std::time::{Duration, Instant};
+
+/// how to sample documents and when to stop harvesting, all documents are harvested by default
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+pub struct SamplingOptions {
+    /// fraction of documents to harvest, e.g. `0.01` for one in a hundred (on average)
+    #[serde(default)]
+    pub sample_rate: Option<f64>,
+    /// harvest a uniform random sample of this many documents, kept in memory until harvesting stops
+    #[serde(default)]
+    pub reservoir: Option<usize>,
+    /// stop after this many (sampled) documents
+    #[serde(default)]
+    pub max_documents: Option<u64>,
+    /// stop after this time
+    #[serde(default, deserialize_with = "deserialize_duration")]
+    pub max_duration: Option<Duration>,
+    /// stop once this many consecutive documents didn't change the hypothesis
+    #[serde(default)]
+    pub stop_when_stable: Option<u64>,
+    /// seed for sampling, to sample the same documents again
+    #[serde(default)]
+    pub seed: Option<u64>,
+}
+
+/// why harvesting stopped
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Stop {
+    MaxDocuments(u64),
+    MaxDuration(Duration),
+    Stable(u64),
+}
+
+/// decides which documents to harvest and when to stop, see [`SamplingOptions`]
+pub struct Sampler {
+    options: SamplingOptions,
+    rng: StdRng,
+    started: Instant,
+    /// number of sampled documents
+    documents: u64,
+    /// number of consecutive documents that didn't change the hypothesis
+    unchanged: u64,
+}
+
+/// a uniform random sample of a fixed number of the items offered (Vitter's algorithm R)
+pub struct Reservoir<T> {
+    capacity: usize,
+    items: Vec<T>,
+    offered: u64,
+    rng: StdRng,
+}
+
+impl SamplingOptions {
+    pub fn validate(&self) -> Result<(), SamplingError> {
+        if let Some(rate) = self.sample_rate {
+            if !(rate > 0.0 && rate <= 1.0) {
+                return Err(SamplingError::InvalidSampleRate(rate));
+            }
+        }
+        match (self.reservoir, self.stop_when_stable) {
+            (Some(0), _) => Err(SamplingError::EmptyReservoir),
+            (Some(_), Some(_)) => Err(SamplingError::StableReservoir),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Display for Stop {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Stop::MaxDocuments(documents) => write!(f, "harvested {documents} documents"),
+            Stop::MaxDuration(duration) => write!(f, "harvested for {duration:?}"),
+            Stop::Stable(documents) => {
+                write!(f, "hypothesis didn't change for {documents} documents")
+            }
+        }
+    }
+}
+
+impl Sampler {
+    /// a sampler for validated options, see [`SamplingOptions::validate`]
+    pub fn new(options: &SamplingOptions) -> Self {
+        Self {
+            options: options.clone(),
+            rng: rng(options.seed),
+            started: Instant::now(),
+            documents: 0,
+            unchanged: 0,
+        }
+    }
+
+    /// whether to harvest the next document, never once stopped
+    pub fn sample(&mut self) -> bool {
+        if self.stopped().is_some() {
+            return false;
+        }
+        let sampled = self
+            .options
+            .sample_rate
+            .is_none_or(|rate| self.rng.random_bool(rate));
+        if sampled {
+            self.documents += 1;
+        }
+        sampled
+    }
+
+    /// record whether harvesting a sampled document changed the hypothesis
+    pub fn observed(&mut self, changed: bool) {
+        self.unchanged = if changed { 0 } else { self.unchanged + 1 };
+    }
+
+    /// why harvesting stopped, if it did
+    pub fn stopped(&self) -> Option<Stop> {
+        let options = &self.options;
+        if let Some(max) = options.max_documents.filter(|max| self.documents >= *max) {
+            return Some(Stop::MaxDocuments(max));
+        }
+        if let Some(stable) = options.stop_when_stable.filter(|n| self.unchanged >= *n) {
+            return Some(Stop::Stable(stable));
+        }
+        options
+            .max_duration
+            .filter(|max| self.started.elapsed() >= *max)
+            .map(Stop::MaxDuration)
+    }
+}
+
+impl<T> Reservoir<T> {
+    pub fn new(capacity: usize, seed: Option<u64>) -> Self {
+        Self {
+            capacity,
+            items: Vec::with_capacity(capacity.min(1024)),
+            offered: 0,
+            // don't sample in lockstep with a sampler of the same seed
+            rng: rng(seed.map(|seed| seed.wrapping_add(1))),
+        }
+    }
+
+    pub fn offer(&mut self, item: T) {
+        self.offered += 1;
+        if self.items.len() < self.capacity {
+            self.items.push(item);
+            return;
+        }
+        let index = self.rng.random_range(0..self.offered);
+        if let Some(slot) = self.items.get_mut(index as usize) {
+            *slot = item;
+        }
+    }
+
+    /// the sampled items, in the order they were offered unless replaced
+    pub fn into_items(self) -> Vec<T> {
+        self.items
+    }
+}
+
+fn rng(seed: Option<u64>) -> StdRng {
+    seed.map_or_else(StdRng::from_os_rng, StdRng::seed_from_u64)
+}
+
+/// parse a duration like `500ms`, `90s`, `15m`, `2h` or `1d`
+pub fn parse_duration(duration: &str) -> Result<Duration, SamplingError> {
+    let invalid = || SamplingError::InvalidDuration(duration.to_string());
+
+    let split = duration
+        .find(|c: char| !c.is_ascii_digit())
+        .ok_or_else(invalid)?;
+    let (value, unit) = duration.split_at(split);
+    let value: u64 = value.parse().map_err(|_| invalid())?;
+    let seconds = match unit {
+        "ms" => return Ok(Duration::from_millis(value)),
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        _ => return Err(invalid()),
+    };
+    value
+        .checked_mul(seconds)
+        .map(Duration::from_secs)
+        .ok_or_else(invalid)
+}
+
+fn deserialize_duration<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Duration>, D::Error> {
+    Option::<String>::deserialize(deserializer)?
+        .map(|duration| parse_duration(&duration).map_err(serde::de::Error::custom))
+        .transpose()
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Reservoir, Sampler, SamplingOptions, Stop, parse_duration};
+    use crate::error::SamplingError;
+    use std::time::Duration;
+    use test_case::test_case;
+
+    #[test_case("500ms", Duration::from_millis(500))]
+    #[test_case("90s", Duration::from_secs(90))]
+    #[test_case("15m", Duration::from_secs(15 * 60))]
+    #[test_case("2h", Duration::from_secs(2 * 60 * 60))]
+    #[test_case("1d", Duration::from_secs(24 * 60 * 60))]
+    fn test_parse_duration(duration: &str, expected: Duration) {
+        assert_eq!(parse_duration(duration).unwrap(), expected);
+    }
+
+    #[test_case("15"; "no unit")]
+    #[test_case("m"; "no value")]
+    #[test_case("1.5h"; "fraction")]
+    #[test_case("3w"; "unknown unit")]
+    fn test_parse_invalid_duration(duration: &str) {
+        assert_eq!(
+            parse_duration(duration).unwrap_err(),
+            SamplingError::InvalidDuration(duration.to_string())
+        );
+    }
+
+    #[test]
+    fn test_validate() {
+        let options = |sample_rate, reservoir, stop_when_stable| SamplingOptions {
+            sample_rate,
+            reservoir,
+            stop_when_stable,
+            ..SamplingOptions::default()
+        };
+
+        assert_eq!(options(Some(1.0), Some(10), None).validate(), Ok(()));
+        assert_eq!(
+            options(Some(0.0), None, None).validate(),
+            Err(SamplingError::InvalidSampleRate(0.0))
+        );
+        assert_eq!(
+            options(Some(1.5), None, None).validate(),
+            Err(SamplingError::InvalidSampleRate(1.5))
+        );
+        assert_eq!(
+            options(None, Some(0), None).validate(),
+            Err(SamplingError::EmptyReservoir)
+        );
+        assert_eq!(
+            options(None, Some(10), Some(100)).validate(),
+            Err(SamplingError::StableReservoir)
+        );
+    }
+
+    #[test]
+    fn test_sample_rate() {
+        let mut sampler = Sampler::new(&SamplingOptions {
+            sample_rate: Some(0.1),
+            seed: Some(42),
+            ..SamplingOptions::default()
+        });
+
+        let sampled = (0..10_000).filter(|_| sampler.sample()).count();
+
+        assert!((800..1200).contains(&sampled), "{sampled}");
+    }
+
+    #[test]
+    fn test_max_documents() {
+        let mut sampler = Sampler::new(&SamplingOptions {
+            max_documents: Some(3),
+            ..SamplingOptions::default()
+        });
+
+        let sampled = (0..10).filter(|_| sampler.sample()).count();
+
+        assert_eq!(sampled, 3);
+        assert_eq!(sampler.stopped(), Some(Stop::MaxDocuments(3)));
+    }
+
+    #[test]
+    fn test_stop_when_stable() {
+        let mut sampler = Sampler::new(&SamplingOptions {
+            stop_when_stable: Some(2),
+            ..SamplingOptions::default()
+        });
+
+        for changed in [true, false, true, false] {
+            assert!(sampler.sample());
+            sampler.observed(changed);
+        }
+        assert_eq!(sampler.stopped(), None);
+        sampler.observed(false);
+
+        assert_eq!(sampler.stopped(), Some(Stop::Stable(2)));
+        assert!(!sampler.sample());
+    }
+
+    #[test]
+    fn test_max_duration() {
+        let sampler = Sampler::new(&SamplingOptions {
+            max_duration: Some(Duration::ZERO),
+            ..SamplingOptions::default()
+        });
+
+        assert_eq!(sampler.stopped(), Some(Stop::MaxDuration(Duration::ZERO)));
+    }
+
+    #[test]
+    fn test_reservoir() {
+        let mut reservoir = Reservoir::new(100, Some(42));
+        for i in 0..10_000 {
+            reservoir.offer(i);
+        }
+
+        let items = reservoir.into_items();
+
+        assert_eq!(items.len(), 100);
+        // a uniform sample, not just the first (or last) items
+        let mean = items.iter().sum::<i32>() / 100;
+        assert!((3000..7000).contains(&mean), "{mean}");
+    }
+
+    #[test]
+    fn test_reservoir_not_full() {
+        let mut reservoir = Reservoir::new(10, None);
+        for i in 0..3 {
+            reservoir.offer(i);
+        }
+
+        assert_eq!(reservoir.into_items(), vec![0, 1, 2]);
+    }
+}
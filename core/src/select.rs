@@ -0,0 +1,253 @@
+//! select the part of documents to harvest, e.g. the items of an API-response's envelope
+//!
+//! Paths are JSON Pointers (RFC 6901, e.g. `/data/items`) or a simple subset of JSONPath: child names
+//! (`$.data.items` or `$['data']['items']`), array indexes (`$.items[0]`) and wildcards (`$.items[*]` or
+//! `$.data.*`).
+
+use crate::error::PathError;
+use serde::Deserialize;
+use serde_json::Value;
+use std::str::FromStr;
+
+/// a path to the values to harvest within a document, the root by default
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct DocumentPath {
+    segments: Vec<Segment>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Segment {
+    /// property of an object, or (for JSON Pointers) index of an array
+    Key(String),
+    /// index of an array
+    Index(usize),
+    /// all elements of an array or all property-values of an object
+    Wildcard,
+}
+
+/// which values of a document to harvest
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+pub struct Selection {
+    #[serde(default)]
+    pub path: DocumentPath,
+    /// harvest the elements of selected arrays as separate documents
+    #[serde(default)]
+    pub explode_arrays: bool,
+}
+
+impl DocumentPath {
+    /// whether the path selects the whole document
+    pub fn is_root(&self) -> bool {
+        self.segments.is_empty()
+    }
+
+    /// the values at the path, nothing if the document doesn't have the path
+    pub fn select<'a>(&self, document: &'a Value) -> Vec<&'a Value> {
+        self.segments
+            .iter()
+            .fold(vec![document], |values, segment| {
+                values
+                    .into_iter()
+                    .flat_map(|value| segment.select(value))
+                    .collect()
+            })
+    }
+}
+
+impl Segment {
+    fn select<'a>(&self, value: &'a Value) -> Vec<&'a Value> {
+        match (self, value) {
+            (Segment::Key(key), Value::Object(properties)) => {
+                properties.get(key).into_iter().collect()
+            }
+            (Segment::Key(key), Value::Array(elements)) => key
+                .parse::<usize>()
+                .ok()
+                .and_then(|index| elements.get(index))
+                .into_iter()
+                .collect(),
+            (Segment::Index(index), Value::Array(elements)) => {
+                elements.get(*index).into_iter().collect()
+            }
+            (Segment::Wildcard, Value::Array(elements)) => elements.iter().collect(),
+            (Segment::Wildcard, Value::Object(properties)) => properties.values().collect(),
+            _ => vec![],
+        }
+    }
+}
+
+impl Selection {
+    /// whether documents are harvested as they are
+    pub fn is_identity(&self) -> bool {
+        self.path.is_root() && !self.explode_arrays
+    }
+
+    /// the values of a document to harvest as separate documents
+    pub fn select<'a>(&self, document: &'a Value) -> Vec<&'a Value> {
+        let selected = self.path.select(document);
+        if !self.explode_arrays {
+            return selected;
+        }
+        selected
+            .into_iter()
+            .flat_map(|value| match value {
+                Value::Array(elements) => elements.iter().collect(),
+                value => vec![value],
+            })
+            .collect()
+    }
+}
+
+impl FromStr for DocumentPath {
+    type Err = PathError;
+
+    fn from_str(path: &str) -> Result<Self, Self::Err> {
+        let segments = match path.chars().next() {
+            None => vec![],
+            Some('/') => parse_pointer(path)?,
+            Some('$') => parse_json_path(path)?,
+            Some(_) => {
+                return Err(PathError::UnknownSyntax {
+                    path: path.to_string(),
+                });
+            }
+        };
+        Ok(DocumentPath { segments })
+    }
+}
+
+impl TryFrom<String> for DocumentPath {
+    type Error = PathError;
+
+    fn try_from(path: String) -> Result<Self, Self::Error> {
+        path.parse()
+    }
+}
+
+fn parse_pointer(pointer: &str) -> Result<Vec<Segment>, PathError> {
+    pointer
+        .split('/')
+        .skip(1)
+        .map(|token| {
+            let mut key = String::with_capacity(token.len());
+            let mut chars = token.chars();
+            while let Some(c) = chars.next() {
+                if c != '~' {
+                    key.push(c);
+                    continue;
+                }
+                match chars.next() {
+                    Some('0') => key.push('~'),
+                    Some('1') => key.push('/'),
+                    _ => {
+                        return Err(PathError::InvalidEscape {
+                            path: pointer.to_string(),
+                        });
+                    }
+                }
+            }
+            Ok(Segment::Key(key))
+        })
+        .collect()
+}
+
+fn parse_json_path(path: &str) -> Result<Vec<Segment>, PathError> {
+    let invalid = |position: usize| PathError::InvalidSyntax {
+        path: path.to_string(),
+        position,
+    };
+
+    let mut segments = vec![];
+    // skip the leading `$`
+    let mut position = 1;
+    while position < path.len() {
+        let rest = &path[position..];
+        if let Some(name) = rest.strip_prefix('.') {
+            let length = name.find(['.', '[']).unwrap_or(name.len());
+            let segment = match &name[..length] {
+                "" => return Err(invalid(position)),
+                "*" => Segment::Wildcard,
+                name => Segment::Key(name.to_string()),
+            };
+            segments.push(segment);
+            position += 1 + length;
+        } else if let Some(subscript) = rest.strip_prefix('[') {
+            let length = subscript.find(']').ok_or_else(|| invalid(position))?;
+            let subscript = &subscript[..length];
+            let quoted = subscript
+                .strip_prefix('\'')
+                .and_then(|s| s.strip_suffix('\''))
+                .or_else(|| {
+                    subscript
+                        .strip_prefix('"')
+                        .and_then(|s| s.strip_suffix('"'))
+                });
+            let segment = match (subscript, quoted) {
+                (_, Some(name)) => Segment::Key(name.to_string()),
+                ("*", None) => Segment::Wildcard,
+                (index, None) => Segment::Index(index.parse().map_err(|_| invalid(position))?),
+            };
+            segments.push(segment);
+            position += 1 + length + 1;
+        } else {
+            return Err(invalid(position));
+        }
+    }
+    Ok(segments)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{DocumentPath, Selection};
+    use crate::error::PathError;
+    use serde_json::{Value, json};
+    use test_case::test_case;
+
+    fn envelope() -> Value {
+        json!({"data": {"items": [{"id": 1}, {"id": 2}], "a/b": {"c~d": true}}, "meta": {"page": 1}})
+    }
+
+    #[test_case("", json!([envelope()]); "root")]
+    #[test_case("$", json!([envelope()]); "json path root")]
+    #[test_case("/data/items", json!([[{"id": 1}, {"id": 2}]]); "pointer")]
+    #[test_case("/data/items/1", json!([{"id": 2}]); "pointer to array index")]
+    #[test_case("/data/a~1b/c~0d", json!([true]); "pointer with escapes")]
+    #[test_case("/data/missing", json!([]); "missing")]
+    #[test_case("$.data.items", json!([[{"id": 1}, {"id": 2}]]); "json path")]
+    #[test_case("$['data'][\"items\"][0]", json!([{"id": 1}]); "json path subscripts")]
+    #[test_case("$.data.items[*].id", json!([1, 2]); "json path wildcard")]
+    #[test_case("$.*.page", json!([1]); "json path object wildcard")]
+    fn test_select(path: &str, expected: Value) {
+        let document = envelope();
+        let path: DocumentPath = path.parse().unwrap();
+
+        let actual: Vec<Value> = path.select(&document).into_iter().cloned().collect();
+
+        assert_eq!(Value::Array(actual), expected);
+    }
+
+    #[test_case("data", PathError::UnknownSyntax { path: "data".into() }; "no prefix")]
+    #[test_case("/a~2", PathError::InvalidEscape { path: "/a~2".into() }; "invalid escape")]
+    #[test_case("$..id", PathError::InvalidSyntax { path: "$..id".into(), position: 1 }; "recursive descent")]
+    #[test_case("$.a[?(@.id)]", PathError::InvalidSyntax { path: "$.a[?(@.id)]".into(), position: 3 }; "filter")]
+    #[test_case("$.a[0", PathError::InvalidSyntax { path: "$.a[0".into(), position: 3 }; "unclosed subscript")]
+    fn test_invalid(path: &str, expected: PathError) {
+        assert_eq!(path.parse::<DocumentPath>().unwrap_err(), expected);
+    }
+
+    #[test]
+    fn test_explode_arrays() {
+        let selection = Selection {
+            path: "/data/items".parse().unwrap(),
+            explode_arrays: true,
+        };
+        let document = envelope();
+
+        let actual: Vec<&Value> = selection.select(&document);
+
+        assert_eq!(actual, vec![&json!({"id": 1}), &json!({"id": 2})]);
+        assert!(!selection.is_identity());
+        assert!(Selection::default().is_identity());
+    }
+}
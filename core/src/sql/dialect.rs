@@ -0,0 +1,148 @@
+use crate::model::{NodeType, StringFormat, StringNode};
+use strum_macros::{Display, EnumString, VariantNames};
+
+/// SQL dialects supported by the DDL renderer
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default, Display, EnumString, VariantNames)]
+#[strum(serialize_all = "kebab-case")]
+pub enum SqlDialect {
+    #[default]
+    Postgres,
+    #[strum(serialize = "bigquery")]
+    BigQuery,
+    #[strum(serialize = "clickhouse")]
+    ClickHouse,
+    #[strum(serialize = "duckdb")]
+    DuckDb,
+}
+
+impl SqlDialect {
+    pub(crate) fn quote(self, identifier: &str) -> String {
+        match self {
+            SqlDialect::Postgres | SqlDialect::DuckDb => {
+                format!("\"{}\"", identifier.replace('"', "\"\""))
+            }
+            SqlDialect::BigQuery | SqlDialect::ClickHouse => {
+                format!("`{}`", identifier.replace('`', "\\`"))
+            }
+        }
+    }
+
+    pub(crate) fn integer(self) -> &'static str {
+        match self {
+            SqlDialect::Postgres | SqlDialect::DuckDb => "BIGINT",
+            SqlDialect::BigQuery => "INT64",
+            SqlDialect::ClickHouse => "Int64",
+        }
+    }
+
+    pub(crate) fn number(self) -> &'static str {
+        match self {
+            SqlDialect::Postgres => "DOUBLE PRECISION",
+            SqlDialect::BigQuery => "FLOAT64",
+            SqlDialect::ClickHouse => "Float64",
+            SqlDialect::DuckDb => "DOUBLE",
+        }
+    }
+
+    pub(crate) fn boolean(self) -> &'static str {
+        match self {
+            SqlDialect::Postgres | SqlDialect::DuckDb => "BOOLEAN",
+            SqlDialect::BigQuery => "BOOL",
+            SqlDialect::ClickHouse => "Bool",
+        }
+    }
+
+    pub(crate) fn string(self, node: &StringNode) -> &'static str {
+        match (self, &node.format) {
+            (SqlDialect::Postgres | SqlDialect::DuckDb, Some(StringFormat::DateTime)) => {
+                "TIMESTAMPTZ"
+            }
+            (SqlDialect::BigQuery, Some(StringFormat::DateTime)) => "TIMESTAMP",
+            (SqlDialect::ClickHouse, Some(StringFormat::DateTime)) => "DateTime64(3)",
+            (SqlDialect::ClickHouse, Some(StringFormat::Date)) => "Date32",
+            (_, Some(StringFormat::Date)) => "DATE",
+            // ClickHouse has no dedicated time-of-day type
+            (SqlDialect::ClickHouse, Some(StringFormat::Time)) => "String",
+            (_, Some(StringFormat::Time)) => "TIME",
+            (SqlDialect::Postgres | SqlDialect::DuckDb, Some(StringFormat::Uuid)) => "UUID",
+            (SqlDialect::ClickHouse, Some(StringFormat::Uuid)) => "UUID",
+            (SqlDialect::Postgres, None) => "TEXT",
+            (SqlDialect::BigQuery, _) => "STRING",
+            (SqlDialect::ClickHouse, None) => "String",
+            (SqlDialect::DuckDb, None) => "VARCHAR",
+        }
+    }
+
+    /// column type for values that can't be expressed with a native type, e.g. mixed unions
+    pub(crate) fn json(self) -> &'static str {
+        match self {
+            SqlDialect::Postgres => "JSONB",
+            SqlDialect::BigQuery | SqlDialect::DuckDb => "JSON",
+            SqlDialect::ClickHouse => "String",
+        }
+    }
+
+    pub(crate) fn array(self, items: &str) -> String {
+        match self {
+            SqlDialect::Postgres | SqlDialect::DuckDb => format!("{items}[]"),
+            SqlDialect::BigQuery => format!("ARRAY<{items}>"),
+            SqlDialect::ClickHouse => format!("Array({items})"),
+        }
+    }
+
+    /// a structured column type, given the already rendered `(name, type)` fields
+    pub(crate) fn structure(self, fields: &[(String, String)]) -> String {
+        let fields = fields
+            .iter()
+            .map(|(name, sql_type)| format!("{} {sql_type}", self.quote(name)))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        match self {
+            SqlDialect::Postgres => self.json().to_string(),
+            SqlDialect::BigQuery => format!("STRUCT<{fields}>"),
+            SqlDialect::ClickHouse => format!("Tuple({fields})"),
+            SqlDialect::DuckDb => format!("STRUCT({fields})"),
+        }
+    }
+
+    /// does the dialect support arrays of the given (already resolved) item type?
+    pub(crate) fn supports_array_of(self, items: &NodeType) -> bool {
+        match (self, items) {
+            // postgres can't nest arrays with differing dimensions, so stick to JSON
+            (SqlDialect::Postgres, NodeType::Array(_) | NodeType::Object(_)) => false,
+            // BigQuery does not allow ARRAY<ARRAY<…>>
+            (SqlDialect::BigQuery, NodeType::Array(_)) => false,
+            _ => true,
+        }
+    }
+
+    /// ClickHouse expresses nullability with a type-wrapper instead of a constraint
+    pub(crate) fn nullable(self, sql_type: String, nullable: bool) -> String {
+        match self {
+            SqlDialect::ClickHouse
+                if nullable
+                    && !sql_type.starts_with("Array(")
+                    && !sql_type.starts_with("Tuple(") =>
+            {
+                format!("Nullable({sql_type})")
+            }
+            _ => sql_type,
+        }
+    }
+
+    pub(crate) fn not_null(self, nullable: bool) -> &'static str {
+        match self {
+            SqlDialect::ClickHouse => "",
+            _ if nullable => "",
+            _ => " NOT NULL",
+        }
+    }
+
+    pub(crate) fn table_suffix(self) -> &'static str {
+        match self {
+            SqlDialect::ClickHouse => "\nENGINE = MergeTree\nORDER BY tuple()",
+            _ => "",
+        }
+    }
+}
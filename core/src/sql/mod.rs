@@ -0,0 +1,324 @@
+//! render a [`SchemaHypothesis`] with an object at its root as SQL `CREATE TABLE` statement
+
+use crate::SchemaHypothesis;
+use crate::error::RenderError;
+use crate::model::{NodeType, ObjectProperty};
+pub use dialect::SqlDialect;
+use std::collections::{BTreeMap, BTreeSet};
+use strum_macros::{Display, EnumString, VariantNames};
+
+mod dialect;
+
+/// how properties holding objects are mapped to columns
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default, Display, EnumString, VariantNames)]
+#[strum(serialize_all = "kebab-case")]
+pub enum NestedObjects {
+    /// one column per (nested) scalar property, named after the path, e.g. `address_street`
+    #[default]
+    Flatten,
+    /// one column per nested object, using the dialect's structured type (JSONB, STRUCT, Tuple)
+    Column,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct SqlOptions {
+    pub dialect: SqlDialect,
+    pub nested_objects: NestedObjects,
+    /// name of the table, defaults to the title of the hypothesis
+    pub table_name: Option<String>,
+}
+
+struct Column {
+    name: String,
+    sql_type: String,
+    nullable: bool,
+}
+
+/// render a `CREATE TABLE` statement for the given hypothesis
+///
+/// # Errors
+///
+/// if the hypothesis is empty or its root is not an object with properties
+pub fn render_sql(schema: &SchemaHypothesis, options: &SqlOptions) -> Result<String, RenderError> {
+    let root = schema.root.as_ref().ok_or(RenderError::EmptyHypothesis)?;
+    let NodeType::Object(root) = root else {
+        return Err(RenderError::RootNotAnObject);
+    };
+    if root.properties.is_empty() {
+        return Err(RenderError::NoProperties);
+    }
+
+    let dialect = options.dialect;
+    let mut columns = vec![];
+    collect_columns(&root.properties, "", false, options, &mut columns);
+
+    // flattened names may clash, e.g. `a_b` and `b` nested in `a`
+    let mut names = BTreeSet::new();
+    let columns = columns
+        .into_iter()
+        .map(|column| {
+            let mut name = column.name.clone();
+            let mut suffix = 2;
+            while !names.insert(name.clone()) {
+                name = format!("{}_{suffix}", column.name);
+                suffix += 1;
+            }
+            format!(
+                "    {} {}{}",
+                dialect.quote(&name),
+                dialect.nullable(column.sql_type, column.nullable),
+                dialect.not_null(column.nullable)
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(",\n");
+
+    let table_name = options.table_name.as_ref().unwrap_or(&schema.title);
+
+    Ok(format!(
+        "CREATE TABLE {} (\n{columns}\n){};",
+        dialect.quote(table_name),
+        dialect.table_suffix()
+    ))
+}
+
+fn collect_columns(
+    properties: &BTreeMap<String, ObjectProperty>,
+    prefix: &str,
+    parent_nullable: bool,
+    options: &SqlOptions,
+    columns: &mut Vec<Column>,
+) {
+    for (key, property) in properties {
+        let (node_type, nullable) = property.node_type.split_null();
+        let nullable = parent_nullable || nullable || !property.required;
+        let name = if prefix.is_empty() {
+            key.clone()
+        } else {
+            format!("{prefix}_{key}")
+        };
+
+        match node_type {
+            // objects without properties have no columns to flatten into, see `sql_type`
+            Some(NodeType::Object(object))
+                if options.nested_objects == NestedObjects::Flatten
+                    && !object.properties.is_empty() =>
+            {
+                collect_columns(&object.properties, &name, nullable, options, columns);
+            }
+            _ => columns.push(Column {
+                name,
+                sql_type: sql_type(options.dialect, node_type),
+                nullable,
+            }),
+        }
+    }
+}
+
+fn sql_type(dialect: SqlDialect, node_type: Option<&NodeType>) -> String {
+    let Some(node_type) = node_type else {
+        // only ever seen null, so there is nothing we know about the type
+        return dialect.json().to_string();
+    };
+
+    match node_type {
+        NodeType::Boolean => dialect.boolean().to_string(),
+        NodeType::Integer(_) => dialect.integer().to_string(),
+        NodeType::Number(_) => dialect.number().to_string(),
+        NodeType::String(s) => dialect.string(s).to_string(),
+        NodeType::Array(array) => match array.items.as_deref().map(NodeType::split_null) {
+            Some((Some(items), _))
+                if !matches!(items, NodeType::Any(_)) && dialect.supports_array_of(items) =>
+            {
+                dialect.array(&sql_type(dialect, Some(items)))
+            }
+            _ => dialect.json().to_string(),
+        },
+        // there's no structured type without fields
+        NodeType::Object(object) if object.properties.is_empty() => dialect.json().to_string(),
+        NodeType::Object(object) => {
+            let fields: Vec<_> = object
+                .properties
+                .iter()
+                .map(|(key, property)| {
+                    let (node_type, nullable) = property.node_type.split_null();
+                    let sql_type = sql_type(dialect, node_type);
+                    // missing fields are null too
+                    let nullable = nullable || !property.required;
+                    (key.clone(), dialect.nullable(sql_type, nullable))
+                })
+                .collect();
+            dialect.structure(&fields)
+        }
+        NodeType::Null | NodeType::Any(_) | NodeType::Recursive(_) => dialect.json().to_string(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{NestedObjects, SqlDialect, SqlOptions, render_sql};
+    use crate::SchemaHypothesis;
+    use crate::error::RenderError;
+    use crate::model::NodeType;
+    use serde_json::{Value, json};
+
+    fn hypothesis(documents: &[Value]) -> SchemaHypothesis {
+        documents.iter().fold(
+            SchemaHypothesis::new("id".to_string(), "events".to_string(), String::new()),
+            |hypothesis, document| hypothesis.merge(NodeType::from(document)),
+        )
+    }
+
+    #[test]
+    fn test_postgres_flatten() {
+        let hypothesis = hypothesis(&[
+            json!({"id": 1, "at": "2000-01-01T00:00:00Z", "address": {"street": "Main", "zip": 1}, "tags": ["a"]}),
+            json!({"id": 2, "at": "2000-01-02T00:00:00Z", "address": {"street": "Side"}, "score": 1.5, "tags": []}),
+        ]);
+
+        let actual = render_sql(&hypothesis, &SqlOptions::default()).unwrap();
+
+        assert_eq!(
+            actual,
+            r#"CREATE TABLE "events" (
+    "address_street" TEXT NOT NULL,
+    "address_zip" BIGINT,
+    "at" TIMESTAMPTZ NOT NULL,
+    "id" BIGINT NOT NULL,
+    "score" DOUBLE PRECISION,
+    "tags" TEXT[] NOT NULL
+);"#
+        );
+    }
+
+    #[test]
+    fn test_nullable_and_mixed() {
+        let hypothesis = hypothesis(&[
+            json!({"name": "a", "value": 1}),
+            json!({"name": null, "value": "one"}),
+        ]);
+
+        let actual = render_sql(&hypothesis, &SqlOptions::default()).unwrap();
+
+        assert_eq!(
+            actual,
+            r#"CREATE TABLE "events" (
+    "name" TEXT,
+    "value" JSONB NOT NULL
+);"#
+        );
+    }
+
+    #[test]
+    fn test_bigquery_struct_column() {
+        let hypothesis = hypothesis(&[json!({
+            "id": "f3fa7e18-549f-4ee1-8aeb-1bb8cbf7e956",
+            "address": {"street": "Main", "zip": 1},
+            "items": [{"sku": "a"}]
+        })]);
+        let options = SqlOptions {
+            dialect: SqlDialect::BigQuery,
+            nested_objects: NestedObjects::Column,
+            table_name: Some("orders".to_string()),
+        };
+
+        let actual = render_sql(&hypothesis, &options).unwrap();
+
+        assert_eq!(
+            actual,
+            r"CREATE TABLE `orders` (
+    `address` STRUCT<`street` STRING, `zip` INT64> NOT NULL,
+    `id` STRING NOT NULL,
+    `items` ARRAY<STRUCT<`sku` STRING>> NOT NULL
+);"
+        );
+    }
+
+    #[test]
+    fn test_clickhouse_nullable() {
+        let hypothesis = hypothesis(&[
+            json!({"id": 1, "day": "2000-01-01"}),
+            json!({"id": 2, "tags": ["a"]}),
+        ]);
+        let options = SqlOptions {
+            dialect: SqlDialect::ClickHouse,
+            ..SqlOptions::default()
+        };
+
+        let actual = render_sql(&hypothesis, &options).unwrap();
+
+        assert_eq!(
+            actual,
+            r"CREATE TABLE `events` (
+    `day` Nullable(Date32),
+    `id` Int64,
+    `tags` Array(String)
+)
+ENGINE = MergeTree
+ORDER BY tuple();"
+        );
+    }
+
+    #[test]
+    fn test_clickhouse_optional_tuple_field() {
+        let hypothesis = hypothesis(&[
+            json!({"id": 1, "address": {"street": "Main", "zip": 1}}),
+            json!({"id": 2, "address": {"street": "Side"}}),
+        ]);
+        let options = SqlOptions {
+            dialect: SqlDialect::ClickHouse,
+            nested_objects: NestedObjects::Column,
+            ..SqlOptions::default()
+        };
+
+        let actual = render_sql(&hypothesis, &options).unwrap();
+
+        assert_eq!(
+            actual,
+            r"CREATE TABLE `events` (
+    `address` Tuple(`street` String, `zip` Nullable(Int64)),
+    `id` Int64
+)
+ENGINE = MergeTree
+ORDER BY tuple();"
+        );
+    }
+
+    #[test]
+    fn test_root_not_an_object() {
+        let hypothesis = hypothesis(&[json!([1, 2])]);
+
+        let actual = render_sql(&hypothesis, &SqlOptions::default());
+
+        assert_eq!(actual, Err(RenderError::RootNotAnObject));
+    }
+
+    #[test]
+    fn test_clashing_and_empty_objects() {
+        let hypothesis = hypothesis(&[json!({"a_b": 1, "a": {"b": 2}, "meta": {}})]);
+        let options = SqlOptions {
+            dialect: SqlDialect::DuckDb,
+            ..SqlOptions::default()
+        };
+
+        let actual = render_sql(&hypothesis, &options).unwrap();
+
+        assert_eq!(
+            actual,
+            r#"CREATE TABLE "events" (
+    "a_b" BIGINT NOT NULL,
+    "a_b_2" BIGINT NOT NULL,
+    "meta" JSON NOT NULL
+);"#
+        );
+    }
+
+    #[test]
+    fn test_root_without_properties() {
+        let hypothesis = hypothesis(&[json!({})]);
+
+        let actual = render_sql(&hypothesis, &SqlOptions::default());
+
+        assert_eq!(actual, Err(RenderError::NoProperties));
+    }
+}
@@ -1,24 +1,60 @@
 use rdkafka::Message;
 use rdkafka::message::OwnedMessage;
 use rdkafka::producer::{FutureProducer, FutureRecord};
-use schema_harvester::model::NodeType;
-use schema_harvester::{SchemaHypothesis, render_schema};
+use schema_harvester::{
+    DecodeError, HarvestOptions, PayloadFormat, Reservoir, Sampler, SamplingOptions,
+    SchemaHypothesis, Selection, decode_payload, load_hypothesis, render_schema, save_hypothesis,
+};
+use std::collections::HashMap;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
 use std::time::Duration;
 use tokio::sync::mpsc::{Receiver, Sender};
-use tracing::info;
+use tokio::time::MissedTickBehavior;
+use tracing::{info, warn};
+
+/// how often to check whether `max_duration` elapsed while no messages arrive
+const STOP_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// the current hypothesis of each source-topic, shared with the management-server
+pub type Hypotheses = Arc<RwLock<HashMap<String, SchemaHypothesis>>>;
+
+/// how to read and sample the messages' payloads
+#[derive(Clone, Debug)]
+pub struct PayloadOptions {
+    pub format: PayloadFormat,
+    pub selection: Selection,
+    pub harvest: HarvestOptions,
+    pub sampling: SamplingOptions,
+}
 
 pub fn init_task(
     producer: &FutureProducer,
     sink_topic: &str,
+    state_dir: Option<&Path>,
+    payload_options: &PayloadOptions,
+    hypotheses: &Hypotheses,
 ) -> impl Fn(String) -> (String, Sender<OwnedMessage>) {
-    |source_topic: String| {
+    move |source_topic: String| {
         let producer = producer.clone();
         let sink_topic = sink_topic.to_string();
+        let state_file = state_dir.map(|dir| dir.join(format!("{source_topic}.json")));
+        let payload_options = payload_options.clone();
+        let hypotheses = hypotheses.clone();
 
         let (tx, rx) = tokio::sync::mpsc::channel::<OwnedMessage>(10);
 
         info!("Subscribing to topic: {}", source_topic);
-        tokio::spawn(task(producer, source_topic.clone(), sink_topic, rx));
+        tokio::spawn(task(
+            producer,
+            source_topic.clone(),
+            sink_topic,
+            state_file,
+            payload_options,
+            hypotheses,
+            rx,
+        ));
 
         (source_topic, tx)
     }
@@ -28,32 +64,99 @@ pub async fn task(
     producer: FutureProducer,
     source_topic: String,
     sink_topic: String,
+    state_file: Option<PathBuf>,
+    payload_options: PayloadOptions,
+    hypotheses: Hypotheses,
     mut rx: Receiver<OwnedMessage>,
 ) {
-    let mut current_hypothesis = SchemaHypothesis::new(
-        "https:://github.com/elmarx/schema-harvester".to_string(),
-        source_topic.clone(),
-        format!("Auto-generated schema for {}", source_topic),
-    );
-
-    while let Some(message) = rx.recv().await {
-        let payload = message.payload();
+    let resumed = match &state_file {
+        Some(state_file) => load_state(state_file).await,
+        None => None,
+    };
+    let mut current_hypothesis = resumed.unwrap_or_else(|| {
+        SchemaHypothesis::new(
+            "https:://github.com/elmarx/schema-harvester".to_string(),
+            source_topic.clone(),
+            format!("Auto-generated schema for {}", source_topic),
+        )
+    });
+    hypotheses
+        .write()
+        .unwrap()
+        .insert(source_topic.clone(), current_hypothesis.clone());
 
-        // TODO: proper error-handling (or rather skip null-messages)
-        let payload = payload.unwrap();
+    let mut sampler = Sampler::new(&payload_options.sampling);
+    let mut reservoir = payload_options
+        .sampling
+        .reservoir
+        .map(|capacity| Reservoir::<Vec<u8>>::new(capacity, payload_options.sampling.seed));
+    let mut stopped = false;
+    // without messages, only time can stop harvesting
+    let check_duration = payload_options.sampling.max_duration.is_some();
+    let mut stop_check = tokio::time::interval(STOP_CHECK_INTERVAL);
+    stop_check.set_missed_tick_behavior(MissedTickBehavior::Delay);
 
-        let payload = serde_json::from_slice(payload);
+    loop {
+        let message = tokio::select! {
+            message = rx.recv() => match message {
+                Some(message) => Some(message),
+                None => break,
+            },
+            _ = stop_check.tick(), if check_duration && !stopped => None,
+        };
 
-        // TODO: proper error-handling
-        let payload = payload.unwrap();
+        let mut changed = match (sampler.stopped(), message) {
+            // keep receiving, the consumer is shared with the other topics
+            _ if stopped => continue,
+            // stopped by time, handled below
+            (Some(_), _) => false,
+            (None, None) => continue,
+            // tombstones (e.g. of compacted topics) have no document to harvest
+            (None, Some(message)) if message.payload().is_none() => continue,
+            (None, Some(_)) if !sampler.sample() => continue,
+            (None, Some(message)) => {
+                let payload = message.payload().unwrap_or_default();
 
-        let hypothesis = NodeType::from(&payload);
+                if let Some(reservoir) = &mut reservoir {
+                    reservoir.offer(payload.to_vec());
+                    false
+                } else {
+                    match observe(&mut current_hypothesis, payload, &payload_options) {
+                        Ok(changed) => {
+                            sampler.observed(changed);
+                            changed
+                        }
+                        Err(e) => {
+                            warn!("Skipping invalid message of {}: {e}", source_topic);
+                            false
+                        }
+                    }
+                }
+            }
+        };
 
-        let new_hypothesis = current_hypothesis.clone().merge(hypothesis);
+        // right away rather than with the next message, so the reservoir of a quiet topic is published too
+        if let Some(stop) = sampler.stopped() {
+            info!("Stopped harvesting {}: {}", source_topic, stop);
+            stopped = true;
+            let sample = reservoir.take().map(Reservoir::into_items);
+            changed |= observe_all(
+                &mut current_hypothesis,
+                sample.unwrap_or_default(),
+                &source_topic,
+                &payload_options,
+            );
+        }
 
         // if the merged hypothesis is a different one than the one we used to know, print it
-        if new_hypothesis != current_hypothesis {
-            current_hypothesis = new_hypothesis;
+        if changed {
+            hypotheses
+                .write()
+                .unwrap()
+                .insert(source_topic.clone(), current_hypothesis.clone());
+            if let Some(state_file) = &state_file {
+                save_state(state_file, &current_hypothesis).await;
+            }
             let current_hypothesis = render_schema(&current_hypothesis);
             let record = FutureRecord::to(&sink_topic)
                 .key(&source_topic)
@@ -67,3 +170,157 @@ pub async fn task(
         }
     }
 }
+
+/// harvest a message's payload, returns whether the hypothesis changed
+///
+/// The hypothesis is left untouched if the payload can't be decoded.
+fn observe(
+    hypothesis: &mut SchemaHypothesis,
+    payload: &[u8],
+    options: &PayloadOptions,
+) -> Result<bool, DecodeError> {
+    let PayloadOptions {
+        format,
+        selection,
+        harvest,
+        ..
+    } = options;
+
+    match format {
+        PayloadFormat::Json if selection.is_identity() => {
+            Ok(hypothesis.observe_slice_with(payload, harvest)?)
+        }
+        format => {
+            let document = decode_payload(payload, *format)?;
+            let mut changed = false;
+            for value in selection.select(&document) {
+                changed |= hypothesis.observe_with(value, harvest);
+            }
+            Ok(changed)
+        }
+    }
+}
+
+/// harvest the sampled payloads of a reservoir, returns whether the hypothesis changed
+fn observe_all(
+    hypothesis: &mut SchemaHypothesis,
+    payloads: Vec<Vec<u8>>,
+    source_topic: &str,
+    options: &PayloadOptions,
+) -> bool {
+    let mut changed = false;
+    for payload in payloads {
+        match observe(hypothesis, &payload, options) {
+            Ok(observed) => changed |= observed,
+            Err(e) => warn!("Skipping invalid message of {}: {e}", source_topic),
+        }
+    }
+    changed
+}
+
+/// the hypothesis saved before, if any
+async fn load_state(state_file: &Path) -> Option<SchemaHypothesis> {
+    match tokio::fs::read_to_string(state_file).await {
+        Ok(saved) => match load_hypothesis(&saved) {
+            Ok(hypothesis) => {
+                info!("Resuming from {}", state_file.display());
+                Some(hypothesis)
+            }
+            Err(e) => {
+                warn!("Ignoring state {}: {e}", state_file.display());
+                None
+            }
+        },
+        Err(e) if e.kind() == ErrorKind::NotFound => None,
+        Err(e) => {
+            warn!("Failed to read state {}: {e}", state_file.display());
+            None
+        }
+    }
+}
+
+/// save the hypothesis, replacing the previous state atomically
+async fn save_state(state_file: &Path, hypothesis: &SchemaHypothesis) {
+    let tmp_file = state_file.with_extension("json.tmp");
+    let result = async {
+        tokio::fs::write(&tmp_file, save_hypothesis(hypothesis)).await?;
+        tokio::fs::rename(&tmp_file, state_file).await
+    };
+    if let Err(e) = result.await {
+        warn!("Failed to save state {}: {e}", state_file.display());
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{PayloadOptions, observe, observe_all};
+    use schema_harvester::{
+        HarvestOptions, PayloadFormat, SamplingOptions, SchemaHypothesis, Selection,
+    };
+
+    fn options(format: PayloadFormat) -> PayloadOptions {
+        PayloadOptions {
+            format,
+            selection: Selection::default(),
+            harvest: HarvestOptions::default(),
+            sampling: SamplingOptions::default(),
+        }
+    }
+
+    #[test]
+    fn test_observe_invalid_payload() {
+        let mut hypothesis =
+            SchemaHypothesis::new("id".to_string(), "orders".to_string(), String::new());
+        observe(
+            &mut hypothesis,
+            br#"{"id": 1}"#,
+            &options(PayloadFormat::Json),
+        )
+        .unwrap();
+        let before = hypothesis.clone();
+
+        assert!(observe(&mut hypothesis, b"{\"id\": ", &options(PayloadFormat::Json)).is_err());
+        assert!(
+            observe(
+                &mut hypothesis,
+                b"\x92\x01",
+                &options(PayloadFormat::MessagePack)
+            )
+            .is_err()
+        );
+        assert_eq!(hypothesis, before);
+    }
+
+    #[test]
+    fn test_observe_all_skips_invalid() {
+        let mut hypothesis =
+            SchemaHypothesis::new("id".to_string(), "orders".to_string(), String::new());
+        let payloads = vec![
+            br#"{"id": 1}"#.to_vec(),
+            b"{\"id\": ".to_vec(),
+            br#"{"id": 2, "note": "a"}"#.to_vec(),
+        ];
+
+        assert!(observe_all(
+            &mut hypothesis,
+            payloads,
+            "orders",
+            &options(PayloadFormat::Json)
+        ));
+        let mut expected =
+            SchemaHypothesis::new("id".to_string(), "orders".to_string(), String::new());
+        observe(
+            &mut expected,
+            br#"{"id": 1}"#,
+            &options(PayloadFormat::Json),
+        )
+        .unwrap();
+        observe(
+            &mut expected,
+            br#"{"id": 2, "note": "a"}"#,
+            &options(PayloadFormat::Json),
+        )
+        .unwrap();
+        assert_eq!(hypothesis, expected);
+    }
+}
@@ -1,10 +1,12 @@
-use crate::generator::init_task;
+use crate::generator::{PayloadOptions, init_task};
 use crate::kafka::{ConsumerExt, init_source};
 use crate::settings::Setting;
 use anyhow::Context;
 use rdkafka::Message;
 use rdkafka::consumer::Consumer;
+use schema_harvester::HarvestOptions;
 use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
 #[cfg(not(target_env = "msvc"))]
 use tikv_jemallocator::Jemalloc;
 
@@ -25,6 +27,17 @@ async fn main() -> anyhow::Result<()> {
 
     log::init(settings.config.logging);
 
+    let sampling = &settings.config.sampling;
+    sampling.validate().context("invalid sampling config")?;
+    if sampling.reservoir.is_some()
+        && sampling.max_documents.is_none()
+        && sampling.max_duration.is_none()
+    {
+        anyhow::bail!(
+            "invalid sampling config: reservoir requires max_documents or max_duration, topics never end"
+        );
+    }
+
     let consumer = init_source(
         &settings.config.kafka,
         &settings.config.kafka_source,
@@ -59,12 +72,29 @@ async fn main() -> anyhow::Result<()> {
         .subscribe(&topics_ref)
         .context("failed to subscribe to topic(s)")?;
 
+    let hypotheses = Arc::new(RwLock::new(HashMap::new()));
+    let payload_options = PayloadOptions {
+        format: settings.config.payload_format,
+        selection: settings.config.selection,
+        harvest: HarvestOptions {
+            embedded_json: settings.config.embedded_json,
+            no_recursion: settings.config.no_recursion,
+        },
+        sampling: settings.config.sampling,
+    };
+
     let topic_tasks: HashMap<_, _> = topics
         .into_iter()
-        .map(init_task(&producer, &sink_topic))
+        .map(init_task(
+            &producer,
+            &sink_topic,
+            settings.config.state_dir.as_deref(),
+            &payload_options,
+            &hypotheses,
+        ))
         .collect();
 
-    tokio::task::spawn(management::run(settings.config.management_port));
+    tokio::task::spawn(management::run(settings.config.management_port, hypotheses));
 
     loop {
         let message = consumer.recv().await?;
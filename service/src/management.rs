@@ -1,17 +1,20 @@
+use crate::generator::Hypotheses;
 use bytes::Bytes;
-use http::{Method, Request, Response, StatusCode};
+use http::header::CONTENT_TYPE;
+use http::{HeaderValue, Method, Request, Response, StatusCode};
 use http_body_util::Full;
 use hyper::body::Incoming;
 use hyper::server::conn::http1;
 use hyper_util::rt::TokioIo;
 use hyper_util::service::TowerToHyperService;
+use schema_harvester::{SchemaHypothesis, render_html, render_markdown, save_hypothesis};
 use std::convert::Infallible;
 use std::net::SocketAddr;
 use tokio::net::TcpListener;
 use tower::ServiceBuilder;
 use tracing::error;
 
-pub async fn run(management_port: u16) -> tokio::io::Result<()> {
+pub async fn run(management_port: u16, hypotheses: Hypotheses) -> tokio::io::Result<()> {
     let addr = SocketAddr::from(([0, 0, 0, 0], management_port));
 
     let listener = TcpListener::bind(addr).await?;
@@ -20,19 +23,11 @@ pub async fn run(management_port: u16) -> tokio::io::Result<()> {
         let (stream, _) = listener.accept().await?;
         let io = TokioIo::new(stream);
 
-        let svc = ServiceBuilder::new().service_fn(move |request: Request<Incoming>| async move {
-            let response = match (request.method(), request.uri().path()) {
-                (&Method::GET, "/healthz") => {
-                    Response::new(Full::new(Bytes::from(r#"{"status": "healthy"}"#)))
-                }
-                _ => {
-                    let mut not_found = Response::new(Full::new(Bytes::new()));
-                    *not_found.status_mut() = StatusCode::NOT_FOUND;
-                    not_found
-                }
-            };
+        let hypotheses = hypotheses.clone();
 
-            Result::<_, Infallible>::Ok(response)
+        let svc = ServiceBuilder::new().service_fn(move |request: Request<Incoming>| {
+            let response = route(request.method(), request.uri().path(), &hypotheses);
+            async move { Result::<_, Infallible>::Ok(response) }
         });
         let svc = TowerToHyperService::new(svc);
 
@@ -43,3 +38,98 @@ pub async fn run(management_port: u16) -> tokio::io::Result<()> {
         });
     }
 }
+
+fn route(method: &Method, path: &str, hypotheses: &Hypotheses) -> Response<Full<Bytes>> {
+    match (method, path) {
+        (&Method::GET, "/healthz") => {
+            Response::new(Full::new(Bytes::from(r#"{"status": "healthy"}"#)))
+        }
+        (&Method::GET, path) if path.starts_with("/topics/") => {
+            let path = &path["/topics/".len()..];
+            let (topic, render, content_type): (_, fn(&SchemaHypothesis) -> String, _) =
+                if let Some(topic) = path.strip_suffix("/docs.html") {
+                    (topic, render_html, "text/html; charset=utf-8")
+                } else if let Some(topic) = path.strip_suffix("/docs.md") {
+                    (topic, render_markdown, "text/markdown; charset=utf-8")
+                } else if let Some(topic) = path.strip_suffix("/hypothesis.json") {
+                    (topic, save_hypothesis, "application/json")
+                } else {
+                    return not_found();
+                };
+
+            let Some(body) = hypotheses.read().unwrap().get(topic).map(render) else {
+                return not_found();
+            };
+
+            let mut response = Response::new(Full::new(Bytes::from(body)));
+            response
+                .headers_mut()
+                .insert(CONTENT_TYPE, HeaderValue::from_static(content_type));
+            response
+        }
+        _ => not_found(),
+    }
+}
+
+fn not_found() -> Response<Full<Bytes>> {
+    let mut not_found = Response::new(Full::new(Bytes::new()));
+    *not_found.status_mut() = StatusCode::NOT_FOUND;
+    not_found
+}
+
+#[cfg(test)]
+mod test {
+    use super::route;
+    use crate::generator::Hypotheses;
+    use http::{Method, StatusCode};
+    use schema_harvester::SchemaHypothesis;
+    use std::collections::HashMap;
+    use std::sync::{Arc, RwLock};
+
+    fn hypotheses() -> Hypotheses {
+        let hypothesis = SchemaHypothesis::new(
+            "id".to_string(),
+            "orders".to_string(),
+            "Auto-generated schema for orders".to_string(),
+        );
+        Arc::new(RwLock::new(HashMap::from([(
+            "orders".to_string(),
+            hypothesis,
+        )])))
+    }
+
+    #[test]
+    fn test_docs() {
+        let hypotheses = hypotheses();
+
+        let html = route(&Method::GET, "/topics/orders/docs.html", &hypotheses);
+        let markdown = route(&Method::GET, "/topics/orders/docs.md", &hypotheses);
+
+        assert_eq!(html.status(), StatusCode::OK);
+        assert_eq!(html.headers()["content-type"], "text/html; charset=utf-8");
+        assert_eq!(markdown.status(), StatusCode::OK);
+        assert_eq!(
+            markdown.headers()["content-type"],
+            "text/markdown; charset=utf-8"
+        );
+    }
+
+    #[test]
+    fn test_hypothesis() {
+        let actual = route(
+            &Method::GET,
+            "/topics/orders/hypothesis.json",
+            &hypotheses(),
+        );
+
+        assert_eq!(actual.status(), StatusCode::OK);
+        assert_eq!(actual.headers()["content-type"], "application/json");
+    }
+
+    #[test]
+    fn test_docs_unknown_topic() {
+        let actual = route(&Method::GET, "/topics/unknown/docs.html", &hypotheses());
+
+        assert_eq!(actual.status(), StatusCode::NOT_FOUND);
+    }
+}
@@ -1,8 +1,10 @@
 use std::collections::HashMap;
+use std::path::PathBuf;
 
 use crate::log;
 use crate::utils::VecExt;
 use config::{ConfigError, Environment, File, FileFormat};
+use schema_harvester::{EmbeddedJson, PayloadFormat, SamplingOptions, Selection};
 use serde::Deserialize;
 
 const DEFAULT_CONFIG: &str = include_str!("../config.default.toml");
@@ -70,6 +72,29 @@ pub struct Config {
 
     pub logging: log::Format,
     pub management_port: u16,
+
+    /// directory to persist the hypothesis of each topic in, to resume after restarts
+    pub state_dir: Option<PathBuf>,
+
+    /// encoding of the messages' payloads
+    #[serde(default)]
+    pub payload_format: PayloadFormat,
+
+    /// part of the messages to harvest
+    #[serde(default)]
+    pub selection: Selection,
+
+    /// which strings to harvest as embedded JSON documents
+    #[serde(default)]
+    pub embedded_json: EmbeddedJson,
+
+    /// don't fold recursive structures into references, harvest each level separately
+    #[serde(default)]
+    pub no_recursion: bool,
+
+    /// how to sample messages and when to stop harvesting a topic
+    #[serde(default)]
+    pub sampling: SamplingOptions,
 }
 
 #[derive(Debug)]